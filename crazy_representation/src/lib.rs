@@ -0,0 +1,260 @@
+use crazy_representation_core::operators::Operator;
+use crazy_representation_core::rational::Rational;
+use crazy_representation_core::rpn_evaluator::evaluate;
+use crazy_representation_core::tasks::{self, TaskQueue};
+use std::cmp::min;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use std::sync::{Mutex, RwLock};
+
+extern crate lazy_static;
+
+lazy_static::lazy_static! {
+    pub static ref BIN_OPERATIONS: RwLock<Mutex<Vec<String>>> = RwLock::new(Mutex::new(Vec::new()));
+    pub static ref OPERANDS: RwLock<Mutex<Vec<String>>> = RwLock::new(Mutex::new(Vec::new()));
+    static ref FOUND: Mutex<Option<String>> = Mutex::new(None);
+}
+
+// set once the number is found, every branch of the recursion returns then
+static STOP: AtomicBool = AtomicBool::new(false);
+static EVALUATED: AtomicU64 = AtomicU64::new(0);
+static SKIPPED: AtomicU64 = AtomicU64::new(0);
+
+pub struct Settings {
+    /// Number of operands pushed before the rest of the search becomes a task,
+    /// all but the last three operands when not set
+    pub split_depth: Option<usize>,
+    pub threads: usize,
+    /// Tasks waiting for a worker, `tasks::default_queue_size` when not set
+    pub queue_size: Option<usize>,
+    /// Allows concatenation of any results, not only of adjacent operands
+    pub concatenate_results: bool,
+    /// Skips trees equal to another one by associativity, see `Operator::is_canonical_with`
+    pub canonical_trees: bool,
+}
+
+#[derive(Clone, Copy)]
+struct Params {
+    step: usize,
+    available: usize,
+    digits_in_row: usize,
+    split_step: usize,
+    concatenate_results: bool,
+    canonical_trees: bool,
+    may_round: bool, // a division or power is appended, results may be rounded
+}
+
+// Number of expressions evaluated by the last search.
+pub fn get_evaluated() -> u64 {
+    EVALUATED.load(Ordering::Relaxed)
+}
+
+// Number of operators the last search did not append as their tree is not canonical.
+pub fn get_skipped() -> u64 {
+    SKIPPED.load(Ordering::Relaxed)
+}
+
+// Appends operands in the given order and operators between them, evaluating every
+// complete expression until one is equal to the number.
+pub fn search_number(
+    operands: Vec<String>,
+    bin_operations: Vec<String>,
+    number: Rational,
+    settings: &Settings,
+) -> Option<String> {
+    STOP.store(false, Ordering::SeqCst);
+    EVALUATED.store(0, Ordering::Relaxed);
+    SKIPPED.store(0, Ordering::Relaxed);
+    *FOUND.lock().unwrap() = None;
+
+    if operands.len() < 2 {
+        let result = operands.join(" ");
+        EVALUATED.fetch_add(1, Ordering::Relaxed);
+        return match evaluate(&result) {
+            Ok(calculated) if number.matches(&result, calculated) => Some(result),
+            _ => None,
+        };
+    }
+
+    let params = Params {
+        step: 3,
+        available: operands.len() - 1,
+        digits_in_row: 2,
+        // `step` is the position of the next operand, so the split step is one more
+        // than the number of operands pushed before it
+        split_step: settings
+            .split_depth
+            .unwrap_or_else(|| operands.len().saturating_sub(3).max(3))
+            + 1,
+        concatenate_results: settings.concatenate_results,
+        canonical_trees: settings.canonical_trees,
+        may_round: false,
+    };
+    let start = format!("{} {}", operands[0], operands[1]);
+
+    {
+        *BIN_OPERATIONS.write().unwrap().lock().unwrap() = bin_operations;
+        *OPERANDS.write().unwrap().lock().unwrap() = operands;
+    }
+
+    let queue = TaskQueue::new(
+        settings.threads,
+        settings
+            .queue_size
+            .unwrap_or_else(|| tasks::default_queue_size(settings.threads)),
+    );
+    gen_equation(number, start, &params, Some(&queue));
+    queue.join();
+    FOUND.lock().unwrap().take()
+}
+
+// (1+2)*(3+4)-5 ^ 6 / (7 - 8) + 9
+// 1 2 + 3 4 + * 5 6 ^ - 7 8 - / 9 +
+
+// Until the split step the recursion runs in the calling thread, every node reached at
+// the split step is queued as a task and explored by a worker without `queue`.
+fn gen_equation(number: Rational, result: String, params: &Params, queue: Option<&TaskQueue>) {
+    if STOP.load(Ordering::Relaxed) {
+        return;
+    }
+    if let Some(queue) = queue {
+        if params.step == params.split_step {
+            let task_params = *params;
+            queue.execute(move || {
+                gen_equation(number, result, &task_params, None);
+            });
+            return;
+        }
+    }
+
+    let operations: Vec<String>;
+    let operands: Vec<String>;
+    {
+        operations = BIN_OPERATIONS.read().unwrap().lock().unwrap().to_vec();
+        operands = OPERANDS.read().unwrap().lock().unwrap().to_vec();
+    }
+    let count = operands.len();
+
+    if params.available == 0 && params.step == (count + 1) {
+        EVALUATED.fetch_add(1, Ordering::Relaxed);
+        if let Ok(calculated) = evaluate(&result) {
+            if number.matches(&result, calculated) {
+                let mut found = FOUND.lock().unwrap();
+                if found.is_none() {
+                    *found = Some(result);
+                }
+                STOP.store(true, Ordering::SeqCst);
+                return;
+            }
+        }
+    }
+    if params.step < count + 1 {
+        let add_number = format!("{} {}", result, operands[params.step - 1]);
+        let new_params = Params {
+            step: params.step + 1,
+            digits_in_row: params.digits_in_row + 1,
+            ..*params
+        };
+        gen_equation(number, add_number, &new_params, queue);
+    }
+    let can_use = min(
+        params.available,
+        params.step - 2 - (count - 1 - params.available),
+    );
+    if can_use > 0 {
+        // the right operand of the operation ends with the last token; the trees are only
+        // equal while every value is an integer, as the rounding of a fraction depends on the tree
+        let right = match params.canonical_trees && !params.may_round {
+            true => result.rsplit(' ').next().and_then(Operator::from_symbol),
+            false => None,
+        };
+        for operation in operations {
+            if let (Some(operator), Some(right)) = (Operator::from_symbol(&operation), right) {
+                if !operator.is_canonical_with(&right) {
+                    SKIPPED.fetch_add(1, Ordering::Relaxed);
+                    continue;
+                }
+            }
+            if let Some(new_params) = next_params(params, &operation) {
+                let add_operation = format!("{} {}", result, operation);
+                gen_equation(number, add_operation, &new_params, queue)
+            }
+        }
+    }
+}
+
+// Params after appending the operation, `None` when the operation cannot be appended.
+fn next_params(params: &Params, operation: &str) -> Option<Params> {
+    let operator = Operator::from_symbol(operation)?;
+    if operator.joins_digits() {
+        // only operands pushed one after another can be concatenated
+        if params.digits_in_row >= 2 {
+            Some(Params {
+                available: params.available - 1,
+                digits_in_row: params.digits_in_row - 1,
+                ..*params
+            })
+        } else if params.concatenate_results {
+            Some(Params {
+                available: params.available - 1,
+                digits_in_row: 0,
+                ..*params
+            })
+        } else {
+            None
+        }
+    } else {
+        Some(Params {
+            available: params.available - 1,
+            digits_in_row: 0,
+            may_round: params.may_round || operator.may_round(),
+            ..*params
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reachable(operations: &[&str], number: Rational, canonical_trees: bool) -> bool {
+        let settings = Settings {
+            split_depth: None,
+            threads: 2,
+            queue_size: None,
+            concatenate_results: false,
+            canonical_trees,
+        };
+        let operands = ["1", "2", "3", "4"].iter().map(|x| x.to_string()).collect();
+        let operations = operations.iter().map(|x| x.to_string()).collect();
+        search_number(operands, operations, number, &settings).is_some()
+    }
+
+    // The searches share the globals of the module, so they are run one after another.
+    #[test]
+    fn canonical_trees_reach_the_same_numbers() {
+        // with division and power present, the trees are only skipped until a value may be rounded
+        let operation_sets: [&[&str]; 3] = [
+            &["_", "+", "-", "*"],
+            &["+", "-", "*", "/"],
+            &["_", "+", "-", "*", "^", "/"],
+        ];
+        let mut numbers: Vec<Rational> = (-20..=100).map(Rational::integer).collect();
+        numbers.extend(
+            ["1/2", "7/3", "-5/4", "1/60"]
+                .iter()
+                .map(|x| x.parse::<Rational>().unwrap()),
+        );
+        for operations in operation_sets.iter() {
+            for number in numbers.iter() {
+                assert_eq!(
+                    reachable(operations, *number, true),
+                    reachable(operations, *number, false),
+                    "{} with {:?}",
+                    number,
+                    operations
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,1159 @@
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::operators::{Operator, REGISTRY};
+use crate::rational::Rational;
+
+const DEFAULT_LOWER_BOUND: f64 = 0.001;
+const DEFAULT_UPPER_BOUND: f64 = 1073741824.0;
+const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Operands {
+    DigitsAscending,
+    DigitsDescending,
+    OneToThree,
+    OneToTwo,
+    OneToSix,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Operations {
+    /// [_, +, -, *, ^, /]
+    All,
+    /// [_, +, -, *]
+    Basic,
+    /// [_, +, -, *, ^, /, %, //]
+    Extended,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Concatenation {
+    /// No concatenation at all
+    Off,
+    /// Only adjacent operands are concatenated, e.g. 12 or 123
+    Digits,
+    /// Results of any subexpressions are concatenated as well, e.g. (1 + 2)3 = 33
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Decimals {
+    /// Operands are written as they are given
+    Off,
+    /// An operand may also be written after a decimal point, 5 as .5
+    Point,
+    /// An operand may also be written after a decimal point or as the repeating part
+    /// of a decimal, 5 as .5 or .(5) = 5/9
+    Repeating,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Trees {
+    /// Every tree is evaluated
+    All,
+    /// Trees equal to another one by associativity of + and * or by moving - out of
+    /// brackets are skipped, e.g. only (1 + 2) + 3 of it and 1 + (2 + 3), until a division
+    /// or power makes the rounding of the trees differ
+    Canonical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Pruning {
+    /// Every branch is explored
+    Off,
+    /// Branches whose interval of reachable values misses the target are cut,
+    /// by the pool strategy only
+    Intervals,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Arithmetic {
+    /// Values are floats, division and power may give fractions
+    Real,
+    /// Every value is a 128-bit integer: division only when exact, power only with
+    /// non-negative exponents, an overflow is an evaluation error
+    Integer,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Powers {
+    /// A negative base has no power with a fractional exponent
+    Principal,
+    /// An exponent p/q with an odd q takes the real q-th root of a negative base,
+    /// so (-8)^(1/3) is -2 and (-8)^(2/3) is 4
+    RealRoots,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Permutations {
+    /// Operands are used in the given order
+    Off,
+    /// All operands in any order
+    All,
+    /// Any non-empty subset of operands in any order
+    Subsets,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Strategy {
+    /// A thread pool task per schema, operators are chosen recursively
+    Pool,
+    /// A thread pool task per operators set, every schema is checked against it
+    OperationSets,
+    /// A thread per schema and operators set, started in bundles
+    ThreadBundles,
+    /// A thread per operators set, started in bundles
+    OperationBundles,
+    /// A thread per schema and last operator, started in bundles
+    Snapshots,
+    /// Operands and operators are appended one by one, the search is split between
+    /// threads at a chosen step
+    Recursive,
+}
+
+impl Strategy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Strategy::Pool => "pool",
+            Strategy::OperationSets => "operation-sets",
+            Strategy::ThreadBundles => "thread-bundles",
+            Strategy::OperationBundles => "operation-bundles",
+            Strategy::Snapshots => "snapshots",
+            Strategy::Recursive => "recursive",
+        }
+    }
+
+    // Strategies with a task per schema accept reordered operands,
+    // the others build the schemes over the operands in the given order.
+    pub fn supports_permutations(&self) -> bool {
+        matches!(self, Strategy::Pool | Strategy::Snapshots)
+    }
+
+    // Only the strategies iterating the schemes themselves can skip to an index.
+    pub fn supports_scheme_ranges(&self) -> bool {
+        matches!(self, Strategy::Pool | Strategy::Snapshots)
+    }
+
+    // The decimal forms of the operands are made with the schemes,
+    // the recursive strategy pushes the operands as they are given.
+    pub fn supports_decimals(&self) -> bool {
+        !matches!(self, Strategy::Recursive)
+    }
+}
+
+/// Indices `start..end` of the schemes processed by a run, in the order schemes are
+/// generated, so a search can be sharded between runs. `end` is open when not set.
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct SchemeRange {
+    pub start: u128,
+    pub end: Option<u128>,
+}
+
+impl FromStr for SchemeRange {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<SchemeRange, String> {
+        let (start, end) = value
+            .split_once("..")
+            .ok_or_else(|| format!("\"{}\" is not a range, expected START..END", value))?;
+        let parse = |bound: &str| {
+            bound
+                .trim()
+                .parse::<u128>()
+                .map_err(|err| format!("invalid bound \"{}\" of the range: {}", bound, err))
+        };
+        let start = if start.trim().is_empty() {
+            0
+        } else {
+            parse(start)?
+        };
+        let end = if end.trim().is_empty() {
+            None
+        } else {
+            Some(parse(end)?)
+        };
+        if let Some(end) = end {
+            if end <= start {
+                return Err(format!("the range {}..{} is empty", start, end));
+            }
+        }
+        Ok(SchemeRange { start, end })
+    }
+}
+
+impl TryFrom<String> for SchemeRange {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<SchemeRange, String> {
+        value.parse()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, ValueEnum, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Format {
+    /// Human readable lines
+    Text,
+    /// A JSON object per line
+    Jsonl,
+    /// Comma-separated values with a header line
+    Csv,
+}
+
+#[derive(Debug)]
+pub enum Mode {
+    Solve {
+        number: Rational,
+        explain: bool,
+    },
+    Table {
+        first: u64,
+        last: u64,
+    },
+    Verify {
+        expression: String,
+        number: Option<Rational>,
+    },
+    Explain {
+        expression: String,
+    },
+    Enumerate,
+    Residues {
+        modulus: u64,
+        residue: Option<u64>,
+    },
+    Schemes {
+        index: Option<u128>,
+        expression: Option<String>,
+    },
+    Coordinate {
+        number: Rational,
+        listen: String,
+        shard_size: Option<u128>,
+    },
+    Work {
+        connect: String,
+    },
+    Benchmark {
+        targets: Vec<Rational>,
+        operand_sets: Vec<Vec<String>>,
+        strategies: Vec<Strategy>,
+        repeats: usize,
+    },
+}
+
+#[derive(Debug)]
+pub struct Config {
+    mode: Mode,
+    bin_operations: Vec<String>,
+    concatenation: Concatenation,
+    decimals: Decimals,
+    trees: Trees,
+    pruning: Pruning,
+    arithmetic: Arithmetic,
+    powers: Powers,
+    operands: Vec<String>,
+    permutations: Permutations,
+    strategy: Strategy,
+    split_depth: Option<usize>,
+    threads: Option<usize>,
+    queue_size: Option<usize>,
+    scheme_range: Option<SchemeRange>,
+    lower_bound: f64,
+    upper_bound: f64,
+    output: Option<String>,
+    sorted: bool,
+    format: Format,
+}
+
+/// Searches for crazy representations of numbers built from a sequence of operands
+#[derive(Parser)]
+#[command(version)]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+    /// Format of solutions and run summaries printed to stdout [default: text]
+    #[arg(long, global = true, value_enum)]
+    format: Option<Format>,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Find an expression equal to the target
+    Solve {
+        /// Number to represent: an integer, a fraction as 22/7 or a power as 2^50
+        #[arg(short, long, allow_hyphen_values = true)]
+        target: Option<Rational>,
+        /// Print the evaluation of the found expression step by step
+        #[arg(long)]
+        explain: bool,
+        #[command(flatten)]
+        search: SearchArgs,
+    },
+    /// Build the table of expressions over the operands and over the reversed operands
+    Table {
+        /// First number of the table
+        #[arg(long)]
+        first: Option<u64>,
+        /// Last number of the table
+        #[arg(long)]
+        last: Option<u64>,
+        #[command(flatten)]
+        search: SearchArgs,
+    },
+    /// Evaluate an RPN expression and print it in infix form
+    Verify {
+        /// Expression in reverse polish notation, e.g. "1 2 _ 3 +"
+        expression: String,
+        /// Number the expression is expected to be equal to
+        #[arg(short, long, allow_hyphen_values = true)]
+        target: Option<Rational>,
+        #[command(flatten)]
+        bounds: BoundsArgs,
+    },
+    /// Evaluate an RPN expression step by step, printing the stack and the infix sub-expression
+    Explain {
+        /// Expression in reverse polish notation, e.g. "1 2 _ 3 +"
+        expression: String,
+        #[command(flatten)]
+        bounds: BoundsArgs,
+    },
+    /// Evaluate every expression and write the reachable numbers to a file
+    Enumerate {
+        #[command(flatten)]
+        search: SearchArgs,
+    },
+    /// Evaluate the expressions modulo a number: find one congruent to the residue
+    /// or list every reachable residue
+    Residues {
+        /// Number the expressions are evaluated modulo
+        #[arg(short, long)]
+        modulus: Option<u64>,
+        /// Residue to find an expression for [default: list every reachable residue]
+        #[arg(short, long)]
+        residue: Option<u64>,
+        #[command(flatten)]
+        search: SearchArgs,
+    },
+    /// Print the number of schemes over the operands, the scheme at an index
+    /// or the index of the scheme of an expression
+    Schemes {
+        /// Index of the scheme to print
+        #[arg(long, conflicts_with = "expression")]
+        index: Option<u128>,
+        /// RPN expression whose scheme index is printed, e.g. "1 2 _ 3 +"
+        #[arg(long)]
+        expression: Option<String>,
+        #[command(flatten)]
+        search: SearchArgs,
+    },
+    /// Split the search for the target into scheme ranges and hand them out to workers
+    Coordinate {
+        /// Number to represent: an integer, a fraction as 22/7 or a power as 2^50
+        #[arg(short, long, allow_hyphen_values = true)]
+        target: Option<Rational>,
+        /// Address workers connect to [default: 127.0.0.1:7878]
+        #[arg(long)]
+        listen: Option<String>,
+        /// Number of schemes in a range given to a worker [default: 1/64 of the schemes]
+        #[arg(long)]
+        shard_size: Option<u128>,
+        #[command(flatten)]
+        search: SearchArgs,
+    },
+    /// Search the scheme ranges given by a coordinator with the pool strategy
+    Worker {
+        /// Address of the coordinator [default: 127.0.0.1:7878]
+        #[arg(long)]
+        connect: Option<String>,
+        /// Number of worker threads [default: derived from the number of cpus]
+        #[arg(short = 'j', long)]
+        threads: Option<usize>,
+        /// Number of tasks waiting for a worker thread [default: 64 per thread]
+        #[arg(long)]
+        queue_size: Option<usize>,
+    },
+    /// Run the search strategies on a fixed set of targets and operands and compare them
+    Benchmark {
+        /// Numbers to search for [default: 10 100 1000]
+        #[arg(long, num_args = 1.., allow_negative_numbers = true)]
+        targets: Vec<Rational>,
+        /// Strategies to compare [default: all]
+        #[arg(long, value_enum, num_args = 1..)]
+        strategies: Vec<Strategy>,
+        /// Number of runs of every search [default: 3]
+        #[arg(long)]
+        repeats: Option<usize>,
+        /// Operands are one-to-three and one-to-six unless --sequence or --operands is given
+        #[command(flatten)]
+        search: SearchArgs,
+    },
+}
+
+// Every option is optional here: a value missing on the command line is taken
+// from the run configuration file and then from the defaults.
+#[derive(Args)]
+struct SearchArgs {
+    /// Run configuration file (TOML, or JSON for a .json extension)
+    #[arg(short, long)]
+    config: Option<PathBuf>,
+    /// Set of binary operations [default: all]
+    #[arg(long, value_enum)]
+    operations: Option<Operations>,
+    /// Explicit list of binary operations by symbol or name, e.g. + or addition,
+    /// overrides --operations
+    #[arg(long, num_args = 1..)]
+    operators: Vec<String>,
+    /// How operands may be concatenated [default: digits]
+    #[arg(long, value_enum)]
+    concatenation: Option<Concatenation>,
+    /// Decimal forms an operand may also be written in [default: off]
+    #[arg(long, value_enum)]
+    decimals: Option<Decimals>,
+    /// Which of the trees equal by associativity are evaluated [default: canonical]
+    #[arg(long, value_enum)]
+    trees: Option<Trees>,
+    /// How branches that cannot reach the target are cut [default: intervals]
+    #[arg(long, value_enum)]
+    pruning: Option<Pruning>,
+    /// Predefined sequence of operands [default: digits-ascending]
+    #[arg(long, value_enum)]
+    sequence: Option<Operands>,
+    /// Custom operands, overrides --sequence
+    #[arg(long, num_args = 1..)]
+    operands: Vec<u64>,
+    /// Whether the operands may be reordered [default: off]
+    #[arg(long, value_enum)]
+    permutations: Option<Permutations>,
+    /// Search strategy [default: pool]
+    #[arg(long, value_enum)]
+    strategy: Option<Strategy>,
+    /// Where the search is split into tasks: operators placed in a schema before it is
+    /// queued, or operands pushed by the recursive strategy, at least 3 [default: derived
+    /// from the number of operands]
+    #[arg(short, long, visible_alias = "step-to-parallel")]
+    split_depth: Option<usize>,
+    /// Number of worker threads [default: derived from the number of cpus and operands]
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+    /// Number of tasks waiting for a worker before new tasks wait to be queued
+    /// [default: 64 per worker]
+    #[arg(long)]
+    queue_size: Option<usize>,
+    /// Only process the schemes with indices in START..END, either bound may be left out
+    /// [default: all]
+    #[arg(long, value_name = "START..END")]
+    schemes: Option<SchemeRange>,
+    /// File to write "number = expression" lines to [default: output.txt for enumerate]
+    #[arg(short, long)]
+    output: Option<String>,
+    /// Write the output file ordered by number once the run is finished
+    #[arg(long)]
+    sorted: bool,
+    #[command(flatten)]
+    bounds: BoundsArgs,
+}
+
+#[derive(Args)]
+struct BoundsArgs {
+    /// Values the evaluator computes with [default: real]
+    #[arg(long, value_enum)]
+    arithmetic: Option<Arithmetic>,
+    /// Powers of negative bases with fractional exponents [default: principal]
+    #[arg(long, value_enum)]
+    powers: Option<Powers>,
+    /// Smallest allowed result of division and power [default: 0.001]
+    #[arg(long, allow_hyphen_values = true)]
+    lower_bound: Option<f64>,
+    /// Largest allowed result of division and power [default: 1073741824]
+    #[arg(long, allow_hyphen_values = true)]
+    upper_bound: Option<f64>,
+}
+
+/// Run configuration file, every field mirrors the command-line option of the same name.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RunFile {
+    target: Option<Rational>,
+    modulus: Option<u64>,
+    residue: Option<u64>,
+    first: Option<u64>,
+    last: Option<u64>,
+    targets: Option<Vec<Rational>>,
+    strategies: Option<Vec<Strategy>>,
+    repeats: Option<usize>,
+    operations: Option<Operations>,
+    operators: Option<Vec<String>>,
+    concatenation: Option<Concatenation>,
+    decimals: Option<Decimals>,
+    trees: Option<Trees>,
+    pruning: Option<Pruning>,
+    arithmetic: Option<Arithmetic>,
+    powers: Option<Powers>,
+    sequence: Option<Operands>,
+    operands: Option<Vec<u64>>,
+    permutations: Option<Permutations>,
+    strategy: Option<Strategy>,
+    #[serde(alias = "step_to_parallel")]
+    split_depth: Option<usize>,
+    threads: Option<usize>,
+    queue_size: Option<usize>,
+    schemes: Option<SchemeRange>,
+    output: Option<String>,
+    sorted: Option<bool>,
+    format: Option<Format>,
+    lower_bound: Option<f64>,
+    upper_bound: Option<f64>,
+}
+
+impl RunFile {
+    fn load(path: &Path) -> Result<RunFile, String> {
+        let content = fs::read_to_string(path)
+            .map_err(|err| format!("cannot read \"{}\": {}", path.display(), err))?;
+        let parsed = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&content).map_err(|err| err.to_string()),
+            _ => toml::from_str(&content).map_err(|err| err.to_string()),
+        };
+        parsed.map_err(|err| format!("invalid run configuration \"{}\": {}", path.display(), err))
+    }
+}
+
+impl Config {
+    /// Parses the command line. `--help` and malformed options are handled by clap itself,
+    /// inconsistent option combinations are returned as an error.
+    pub fn from_args() -> Result<Config, String> {
+        Config::from_cli(Cli::parse())
+    }
+
+    /// Parses the given arguments, the first one being the program name. Malformed options
+    /// are returned as an error too.
+    pub fn parse_from<I, T>(args: I) -> Result<Config, String>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        Config::from_cli(Cli::try_parse_from(args).map_err(|err| err.to_string())?)
+    }
+
+    fn from_cli(cli: Cli) -> Result<Config, String> {
+        let format = cli.format;
+        match cli.command {
+            Command::Solve {
+                target,
+                explain,
+                search,
+            } => {
+                let file = Config::load_run_file(&search)?;
+                let number = target
+                    .or(file.target)
+                    .ok_or("the target is required: pass --target or set `target` in the run configuration")?;
+                Config::from_search_args(Mode::Solve { number, explain }, search, file, format)
+            }
+            Command::Table {
+                first,
+                last,
+                search,
+            } => {
+                let file = Config::load_run_file(&search)?;
+                let first = first
+                    .or(file.first)
+                    .ok_or("the first number is required: pass --first or set `first` in the run configuration")?;
+                let last = last
+                    .or(file.last)
+                    .ok_or("the last number is required: pass --last or set `last` in the run configuration")?;
+                if last < first {
+                    return Err(format!(
+                        "--last ({}) must not be less than --first ({})",
+                        last, first
+                    ));
+                }
+                let config =
+                    Config::from_search_args(Mode::Table { first, last }, search, file, format)?;
+                if config.permutations != Permutations::Off {
+                    return Err("--permutations cannot be used to build a table".to_string());
+                }
+                Ok(config)
+            }
+            Command::Verify {
+                expression,
+                target,
+                bounds,
+            } => Config::from_expression(
+                Mode::Verify {
+                    expression,
+                    number: target,
+                },
+                bounds,
+                format,
+            ),
+            Command::Explain { expression, bounds } => {
+                Config::from_expression(Mode::Explain { expression }, bounds, format)
+            }
+            Command::Benchmark {
+                targets,
+                strategies,
+                repeats,
+                search,
+            } => {
+                let file = Config::load_run_file(&search)?;
+                let targets = if !targets.is_empty() {
+                    targets
+                } else {
+                    file.targets.clone().unwrap_or_else(|| {
+                        [10, 100, 1000].iter().map(|x| Rational::from(*x)).collect()
+                    })
+                };
+                let strategies = if !strategies.is_empty() {
+                    strategies
+                } else {
+                    file.strategies
+                        .clone()
+                        .unwrap_or_else(|| Strategy::value_variants().to_vec())
+                };
+                let repeats = repeats.or(file.repeats).unwrap_or(3);
+                if targets.is_empty() || strategies.is_empty() || repeats == 0 {
+                    return Err(
+                        "a benchmark needs at least one target, one strategy and one run"
+                            .to_string(),
+                    );
+                }
+                let custom_operands = !search.operands.is_empty()
+                    || search.sequence.is_some()
+                    || file.operands.is_some()
+                    || file.sequence.is_some();
+
+                let mut config = Config::from_search_args(Mode::Enumerate, search, file, format)?;
+                let operand_sets = if custom_operands {
+                    vec![config.operands.clone()]
+                } else {
+                    vec![
+                        Config::generate_operands(Operands::OneToThree),
+                        Config::generate_operands(Operands::OneToSix),
+                    ]
+                };
+                for strategy in strategies.iter() {
+                    config.check_strategy(*strategy)?;
+                }
+                config.mode = Mode::Benchmark {
+                    targets,
+                    operand_sets,
+                    strategies,
+                    repeats,
+                };
+                Ok(config)
+            }
+            Command::Enumerate { search } => {
+                let file = Config::load_run_file(&search)?;
+                let mut config = Config::from_search_args(Mode::Enumerate, search, file, format)?;
+                if config.output.is_none() {
+                    config.output = Some(String::from("output.txt"));
+                }
+                Ok(config)
+            }
+            Command::Residues {
+                modulus,
+                residue,
+                search,
+            } => {
+                let file = Config::load_run_file(&search)?;
+                let modulus = modulus
+                    .or(file.modulus)
+                    .ok_or("the modulus is required: pass --modulus or set `modulus` in the run configuration")?;
+                if modulus == 0 {
+                    return Err("--modulus must be positive".to_string());
+                }
+                let residue = residue.or(file.residue);
+                if let Some(residue) = residue {
+                    if residue >= modulus {
+                        return Err(format!(
+                            "--residue ({}) must be less than --modulus ({})",
+                            residue, modulus
+                        ));
+                    }
+                }
+                Config::from_search_args(Mode::Residues { modulus, residue }, search, file, format)
+            }
+            Command::Coordinate {
+                target,
+                listen,
+                shard_size,
+                search,
+            } => {
+                let file = Config::load_run_file(&search)?;
+                let number = target
+                    .or(file.target)
+                    .ok_or("the target is required: pass --target or set `target` in the run configuration")?;
+                if shard_size == Some(0) {
+                    return Err("--shard-size must be positive".to_string());
+                }
+                let mode = Mode::Coordinate {
+                    number,
+                    listen: listen.unwrap_or_else(|| DEFAULT_ADDRESS.to_string()),
+                    shard_size,
+                };
+                let config = Config::from_search_args(mode, search, file, format)?;
+                if config.permutations != Permutations::Off || config.strategy != Strategy::Pool {
+                    return Err(
+                        "the distributed search runs the pool strategy without permutations"
+                            .to_string(),
+                    );
+                }
+                Ok(config)
+            }
+            Command::Worker {
+                connect,
+                threads,
+                queue_size,
+            } => {
+                if threads == Some(0) || queue_size == Some(0) {
+                    return Err("--threads and --queue-size must be positive".to_string());
+                }
+                // everything else about the search comes from the coordinator
+                let connect = connect.unwrap_or_else(|| DEFAULT_ADDRESS.to_string());
+                let bounds = BoundsArgs {
+                    arithmetic: None,
+                    powers: None,
+                    lower_bound: None,
+                    upper_bound: None,
+                };
+                let mut config = Config::from_expression(Mode::Work { connect }, bounds, format)?;
+                config.threads = threads;
+                config.queue_size = queue_size;
+                Ok(config)
+            }
+            Command::Schemes {
+                index,
+                expression,
+                search,
+            } => {
+                let file = Config::load_run_file(&search)?;
+                let mut config = Config::from_search_args(Mode::Enumerate, search, file, format)?;
+                config.mode = Mode::Schemes { index, expression };
+                Ok(config)
+            }
+        }
+    }
+
+    fn load_run_file(search: &SearchArgs) -> Result<RunFile, String> {
+        match &search.config {
+            Some(path) => RunFile::load(path),
+            None => Ok(RunFile::default()),
+        }
+    }
+
+    // Modes working on a single expression only need the rules of the evaluator.
+    fn from_expression(
+        mode: Mode,
+        bounds: BoundsArgs,
+        format: Option<Format>,
+    ) -> Result<Config, String> {
+        let (lower_bound, upper_bound) = Config::resolve_bounds(&bounds, &RunFile::default())?;
+        Ok(Config {
+            mode,
+            bin_operations: Config::preset_operations(Operations::All),
+            concatenation: Concatenation::Digits,
+            decimals: Decimals::Off,
+            trees: Trees::Canonical,
+            pruning: Pruning::Intervals,
+            arithmetic: bounds.arithmetic.unwrap_or(Arithmetic::Real),
+            powers: bounds.powers.unwrap_or(Powers::Principal),
+            operands: Vec::new(),
+            permutations: Permutations::Off,
+            strategy: Strategy::Pool,
+            split_depth: None,
+            threads: None,
+            queue_size: None,
+            scheme_range: None,
+            lower_bound,
+            upper_bound,
+            output: None,
+            sorted: false,
+            format: format.unwrap_or(Format::Text),
+        })
+    }
+
+    fn from_search_args(
+        mode: Mode,
+        search: SearchArgs,
+        file: RunFile,
+        format: Option<Format>,
+    ) -> Result<Config, String> {
+        let (lower_bound, upper_bound) = Config::resolve_bounds(&search.bounds, &file)?;
+
+        let threads = search.threads.or(file.threads);
+        if threads == Some(0) {
+            return Err("--threads must be positive".to_string());
+        }
+        let queue_size = search.queue_size.or(file.queue_size);
+        if queue_size == Some(0) {
+            return Err("--queue-size must be positive".to_string());
+        }
+
+        let permutations = search
+            .permutations
+            .or(file.permutations)
+            .unwrap_or(Permutations::Off);
+        let strategy = search.strategy.or(file.strategy).unwrap_or(Strategy::Pool);
+
+        let scheme_range = search.schemes.or(file.schemes);
+        if scheme_range.is_some() && permutations != Permutations::Off {
+            return Err("--schemes cannot be used with --permutations".to_string());
+        }
+
+        let operands = if !search.operands.is_empty() {
+            search.operands
+        } else {
+            file.operands.unwrap_or_default()
+        };
+        let operands: Vec<String> = if operands.is_empty() {
+            let sequence = search
+                .sequence
+                .or(file.sequence)
+                .unwrap_or(Operands::DigitsAscending);
+            Config::generate_operands(sequence)
+        } else {
+            operands.iter().map(|x| x.to_string()).collect()
+        };
+
+        let split_depth = search.split_depth.or(file.split_depth);
+        if let Some(depth) = split_depth {
+            if depth > operands.len() {
+                return Err(format!(
+                    "--split-depth ({}) must not exceed the number of operands ({})",
+                    depth,
+                    operands.len()
+                ));
+            }
+        }
+
+        let operators = if !search.operators.is_empty() {
+            search.operators
+        } else {
+            file.operators.unwrap_or_default()
+        };
+        let mut bin_operations = if operators.is_empty() {
+            let operations = search
+                .operations
+                .or(file.operations)
+                .unwrap_or(Operations::All);
+            Config::preset_operations(operations)
+        } else {
+            Config::check_operators(operators)?
+        };
+
+        let concatenation = search
+            .concatenation
+            .or(file.concatenation)
+            .unwrap_or(Concatenation::Digits);
+        if concatenation == Concatenation::Off {
+            bin_operations.retain(|x| !Operator::from_symbol(x).is_some_and(|x| x.joins_digits()));
+        }
+        if bin_operations.is_empty() {
+            return Err("at least one operation is required".to_string());
+        }
+
+        let arithmetic = search
+            .bounds
+            .arithmetic
+            .or(file.arithmetic)
+            .unwrap_or(Arithmetic::Real);
+        let decimals = search.decimals.or(file.decimals).unwrap_or(Decimals::Off);
+        if decimals != Decimals::Off {
+            if arithmetic == Arithmetic::Integer {
+                return Err("--decimals cannot be used with --arithmetic integer".to_string());
+            }
+            // a residue is only defined for integer operands
+            if matches!(mode, Mode::Residues { .. }) {
+                return Err("--decimals cannot be used with residues".to_string());
+            }
+        }
+
+        let config = Config {
+            mode,
+            bin_operations,
+            concatenation,
+            decimals,
+            trees: search.trees.or(file.trees).unwrap_or(Trees::Canonical),
+            pruning: search
+                .pruning
+                .or(file.pruning)
+                .unwrap_or(Pruning::Intervals),
+            arithmetic,
+            powers: search
+                .bounds
+                .powers
+                .or(file.powers)
+                .unwrap_or(Powers::Principal),
+            operands,
+            permutations,
+            strategy,
+            split_depth,
+            threads,
+            queue_size,
+            scheme_range,
+            lower_bound,
+            upper_bound,
+            output: search.output.or(file.output),
+            sorted: search.sorted || file.sorted.unwrap_or(false),
+            format: format.or(file.format).unwrap_or(Format::Text),
+        };
+        // enumerate and residues walk the schemes whatever the strategy,
+        // benchmark checks every strategy it compares instead
+        if !matches!(config.mode, Mode::Enumerate | Mode::Residues { .. }) {
+            config.check_strategy(strategy)?;
+        }
+        Ok(config)
+    }
+
+    // Options of the search the strategy cannot run with.
+    fn check_strategy(&self, strategy: Strategy) -> Result<(), String> {
+        if self.permutations != Permutations::Off && !strategy.supports_permutations() {
+            return Err(format!(
+                "--permutations is not supported by the {} strategy",
+                strategy.name()
+            ));
+        }
+        if self.scheme_range.is_some() && !strategy.supports_scheme_ranges() {
+            return Err(format!(
+                "--schemes is not supported by the {} strategy",
+                strategy.name()
+            ));
+        }
+        if self.decimals != Decimals::Off && !strategy.supports_decimals() {
+            return Err(format!(
+                "--decimals is not supported by the {} strategy",
+                strategy.name()
+            ));
+        }
+        // the recursive strategy pushes two operands before it places the first operator
+        if let (Strategy::Recursive, Some(depth)) = (strategy, self.split_depth) {
+            if depth < 3 {
+                return Err(format!(
+                    "--split-depth ({}) must be at least 3 for the {} strategy",
+                    depth,
+                    strategy.name()
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_bounds(bounds: &BoundsArgs, file: &RunFile) -> Result<(f64, f64), String> {
+        let lower_bound = bounds
+            .lower_bound
+            .or(file.lower_bound)
+            .unwrap_or(DEFAULT_LOWER_BOUND);
+        let upper_bound = bounds
+            .upper_bound
+            .or(file.upper_bound)
+            .unwrap_or(DEFAULT_UPPER_BOUND);
+        if lower_bound.partial_cmp(&upper_bound) != Some(Ordering::Less) {
+            return Err(format!(
+                "--lower-bound ({}) must be less than --upper-bound ({})",
+                lower_bound, upper_bound
+            ));
+        }
+        Ok((lower_bound, upper_bound))
+    }
+
+    // Symbols of the operators, given by symbol or by name.
+    fn check_operators(operators: Vec<String>) -> Result<Vec<String>, String> {
+        let mut checked = Vec::<String>::new();
+        for operator in operators {
+            let symbol =
+                match Operator::from_symbol(&operator).or_else(|| Operator::from_name(&operator)) {
+                    // schemes are binary trees, so the search only takes binary operators
+                    Some(found) if found.arity() == 2 => found.symbol().to_string(),
+                    Some(_) => return Err(format!("\"{}\" is not a binary operation", operator)),
+                    None => {
+                        let supported: Vec<String> = REGISTRY
+                            .iter()
+                            .map(|x| format!("{} ({})", x.symbol(), x.name()))
+                            .collect();
+                        return Err(format!(
+                            "unknown operation \"{}\", supported: {}",
+                            operator,
+                            supported.join(", ")
+                        ));
+                    }
+                };
+            if !checked.contains(&symbol) {
+                checked.push(symbol);
+            }
+        }
+        Ok(checked)
+    }
+
+    fn preset_operations(operations: Operations) -> Vec<String> {
+        REGISTRY
+            .iter()
+            .filter(|x| x.arity() == 2)
+            .filter(|x| match operations {
+                Operations::All => x.is_default(),
+                Operations::Basic => x.is_basic(),
+                Operations::Extended => true,
+            })
+            .map(|x| x.symbol().to_string())
+            .collect()
+    }
+
+    pub fn get_operations(&self) -> Vec<String> {
+        self.bin_operations.clone()
+    }
+
+    pub fn get_concatenation(&self) -> Concatenation {
+        self.concatenation
+    }
+
+    pub fn get_decimals(&self) -> Decimals {
+        self.decimals
+    }
+
+    pub fn get_trees(&self) -> Trees {
+        self.trees
+    }
+
+    pub fn get_pruning(&self) -> Pruning {
+        self.pruning
+    }
+
+    pub fn get_arithmetic(&self) -> Arithmetic {
+        self.arithmetic
+    }
+
+    pub fn get_powers(&self) -> Powers {
+        self.powers
+    }
+
+    pub fn get_format(&self) -> Format {
+        self.format
+    }
+
+    pub fn get_output(&self) -> Option<&str> {
+        self.output.as_deref()
+    }
+
+    pub fn is_sorted(&self) -> bool {
+        self.sorted
+    }
+
+    pub fn get_mode(&self) -> &Mode {
+        &self.mode
+    }
+
+    pub fn get_permutations(&self) -> Permutations {
+        self.permutations
+    }
+
+    pub fn get_strategy(&self) -> Strategy {
+        self.strategy
+    }
+
+    pub fn get_operands(&self) -> Vec<String> {
+        self.operands.clone()
+    }
+
+    pub fn get_split_depth(&self) -> Option<usize> {
+        self.split_depth
+    }
+
+    pub fn get_queue_size(&self) -> Option<usize> {
+        self.queue_size
+    }
+
+    pub fn get_scheme_range(&self) -> Option<SchemeRange> {
+        self.scheme_range
+    }
+
+    pub fn get_threads(&self) -> Option<usize> {
+        self.threads
+    }
+
+    pub fn get_bounds(&self) -> (f64, f64) {
+        (self.lower_bound, self.upper_bound)
+    }
+
+    pub fn generate_operands(kind: Operands) -> Vec<String> {
+        match kind {
+            Operands::DigitsAscending => (1..10).map(|x| x.to_string()).collect(),
+            Operands::DigitsDescending => (1..10).rev().map(|x| x.to_string()).collect(),
+            Operands::OneToThree => (1..4).map(|x| x.to_string()).collect(),
+            Operands::OneToTwo => (1..3).map(|x| x.to_string()).collect(),
+            Operands::OneToSix => (1..7).map(|x| x.to_string()).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Config, String> {
+        Config::parse_from(
+            std::iter::once("crazy_representation_schemes").chain(args.iter().cloned()),
+        )
+    }
+
+    #[test]
+    fn strategy_options() {
+        assert!(parse(&["solve", "-t", "1", "--strategy", "pool", "-s", "0"]).is_ok());
+        assert!(parse(&["solve", "-t", "1", "--strategy", "recursive", "-s", "3"]).is_ok());
+        assert!(parse(&["solve", "-t", "1", "--strategy", "recursive", "-s", "2"]).is_err());
+        assert!(parse(&["enumerate", "--strategy", "recursive", "-s", "2"]).is_ok());
+        for options in [
+            ["--permutations", "all"],
+            ["--schemes", "0..10"],
+            ["--decimals", "point"],
+            ["--split-depth", "2"],
+        ] {
+            let (option, value) = (options[0], options[1]);
+            assert!(parse(&["benchmark", "--strategies", "pool", option, value]).is_ok());
+            let strategies = ["benchmark", "--strategies", "pool", "recursive"];
+            assert!(parse(&[&strategies[..], &[option, value]].concat()).is_err());
+        }
+    }
+
+    #[test]
+    fn negative_bounds() {
+        let config = parse(&["verify", "--lower-bound", "-10", "0 8 - 1 3 / ^"]).unwrap();
+        assert_eq!(config.get_bounds(), (-10.0, DEFAULT_UPPER_BOUND));
+        let config = parse(&[
+            "solve",
+            "-t",
+            "-7",
+            "--lower-bound=-1e9",
+            "--upper-bound",
+            "-1",
+        ])
+        .unwrap();
+        assert_eq!(config.get_bounds(), (-1e9, -1.0));
+        assert!(parse(&[
+            "solve",
+            "-t",
+            "1",
+            "--lower-bound",
+            "-1",
+            "--upper-bound",
+            "-2"
+        ])
+        .is_err());
+    }
+}
@@ -0,0 +1,295 @@
+use crate::operators::Operator;
+use crate::rpn_evaluator::{apply_value, get_bounds, parse_operand};
+
+// Closed range of values, possibly unbounded.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interval {
+    pub low: f64,
+    pub high: f64,
+}
+
+impl Interval {
+    const ANY: Interval = Interval {
+        low: f64::NEG_INFINITY,
+        high: f64::INFINITY,
+    };
+
+    pub fn point(value: f64) -> Interval {
+        Interval {
+            low: value,
+            high: value,
+        }
+    }
+
+    pub fn contains(&self, value: f64) -> bool {
+        self.low <= value && value <= self.high
+    }
+
+    // Whether the interval meets the range from `low` to `high`.
+    pub fn meets(&self, low: f64, high: f64) -> bool {
+        self.low <= high && low <= self.high
+    }
+
+    fn is_point(&self) -> bool {
+        self.low == self.high
+    }
+
+    fn union(&self, other: &Interval) -> Interval {
+        Interval {
+            low: self.low.min(other.low),
+            high: self.high.max(other.high),
+        }
+    }
+
+    // Narrows the interval to the given range, `None` when they do not meet.
+    fn clamp(&self, low: f64, high: f64) -> Option<Interval> {
+        let clamped = Interval {
+            low: self.low.max(low),
+            high: self.high.min(high),
+        };
+        if clamped.low <= clamped.high {
+            Some(clamped)
+        } else {
+            None
+        }
+    }
+
+    fn from_bounds(bounds: [f64; 4]) -> Interval {
+        if bounds.iter().any(|x| x.is_nan()) {
+            return Interval::ANY;
+        }
+        Interval {
+            low: bounds.iter().cloned().fold(f64::INFINITY, f64::min),
+            high: bounds.iter().cloned().fold(f64::NEG_INFINITY, f64::max),
+        }
+    }
+}
+
+// Digits the concatenation writes for a non-negative operand, at least one.
+fn digits(value: f64) -> i32 {
+    if value < 10.0 {
+        1
+    } else {
+        value.log10().floor() as i32 + 1
+    }
+}
+
+// Values the operator can give for operands in the intervals, `None` when it gives none.
+// Both the rounding of floats and `x * 10^digits(y) + y` of the concatenation are monotone,
+// so the bounds computed from the ends hold for every operand in between.
+fn apply_interval(
+    operator: &Operator,
+    operand1: &Interval,
+    operand2: &Interval,
+    bounds: (f64, f64),
+) -> Option<Interval> {
+    if operand1.is_point() && operand2.is_point() {
+        return apply_value(operator, &[operand1.low, operand2.low], bounds)
+            .ok()
+            .map(Interval::point);
+    }
+    let (lower_bound, upper_bound) = bounds;
+    let (a, b, c, d) = (operand1.low, operand1.high, operand2.low, operand2.high);
+    match operator.symbol() {
+        "+" => Some(Interval {
+            low: a + c,
+            high: b + d,
+        }),
+        "-" => Some(Interval {
+            low: a - d,
+            high: b - c,
+        }),
+        "*" => Some(Interval::from_bounds([a * c, a * d, b * c, b * d])),
+        "/" => {
+            let quotient = if c > 0.0 || d < 0.0 {
+                Interval::from_bounds([a / c, a / d, b / c, b / d])
+            } else {
+                Interval::ANY
+            };
+            quotient.clamp(lower_bound, upper_bound)
+        }
+        "_" => {
+            if b < 0.0 || d < 0.0 {
+                return None;
+            }
+            // a zero, also a negative one, is written as 0: 0 _ y is y
+            let y = c.max(0.0);
+            let low = if a <= 0.0 {
+                y
+            } else {
+                a * 10f64.powi(digits(y)) + y
+            };
+            Some(Interval {
+                low,
+                high: b * 10f64.powi(digits(d)) + d,
+            })
+        }
+        "^" => Interval::ANY.clamp(lower_bound, upper_bound),
+        // nothing is known of the values of other operators
+        _ => Some(Interval::ANY),
+    }
+}
+
+// Interval of the values an RPN expression can still take when the `None` tokens are
+// operators yet to be chosen from `operators`, `None` when no choice gives a value.
+// The bounds are conservative: a value outside them is never reached, a value inside
+// them may be.
+pub fn reachable(tokens: &[Option<&str>], operators: &[Operator]) -> Option<Interval> {
+    let bounds = get_bounds();
+    let mut stack: Vec<Interval> = Vec::new();
+    for token in tokens {
+        let operator = match token {
+            Some(token) => match Operator::from_symbol(token) {
+                Some(operator) => Some(operator),
+                None => {
+                    stack.push(Interval::point(parse_operand(token)?));
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let operand2 = stack.pop()?;
+        let operand1 = stack.pop()?;
+        let result = match operator {
+            Some(operator) => apply_interval(&operator, &operand1, &operand2, bounds)?,
+            None => operators
+                .iter()
+                .filter_map(|operator| apply_interval(operator, &operand1, &operand2, bounds))
+                .reduce(|x, y| x.union(&y))?,
+        };
+        stack.push(result);
+    }
+    match stack.as_slice() {
+        [result] => Some(*result),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpn_evaluator::evaluate;
+
+    // Every RPN expression over the operands in their order with the operators.
+    fn expressions(operands: &[&str], operators: &[Operator]) -> Vec<Vec<String>> {
+        fn extend(
+            tokens: Vec<String>,
+            depth: usize,
+            operands: &[&str],
+            operators: &[Operator],
+            found: &mut Vec<Vec<String>>,
+        ) {
+            if operands.is_empty() && depth == 1 {
+                found.push(tokens.clone());
+            }
+            if let Some((operand, rest)) = operands.split_first() {
+                let mut pushed = tokens.clone();
+                pushed.push(operand.to_string());
+                extend(pushed, depth + 1, rest, operators, found);
+            }
+            if depth >= 2 {
+                for operator in operators {
+                    let mut applied = tokens.clone();
+                    applied.push(operator.symbol().to_string());
+                    extend(applied, depth - 1, operands, operators, found);
+                }
+            }
+        }
+        let mut found = Vec::new();
+        extend(Vec::new(), 0, operands, operators, &mut found);
+        found
+    }
+
+    #[test]
+    fn points() {
+        let tokens = [Some("3"), Some("4"), Some("_"), Some("2"), Some("/")];
+        assert_eq!(reachable(&tokens, &[]), Some(Interval::point(17.0)));
+        assert_eq!(reachable(&[Some("1"), Some("0"), Some("/")], &[]), None);
+        assert_eq!(reachable(&[Some("1"), Some("+")], &[]), None);
+    }
+
+    #[test]
+    fn concatenation() {
+        // 0 - 1 cannot be concatenated, 0 _ 7 is 7 and 1 _ 7 is 17
+        let tokens = [Some("0"), Some("1"), None, Some("7"), Some("_")];
+        let operators = [
+            Operator::ADDITION,
+            Operator::SUBSTRACTION,
+            Operator::MULTIPLICATION,
+        ];
+        assert_eq!(
+            reachable(&tokens, &operators),
+            Some(Interval {
+                low: 7.0,
+                high: 17.0
+            })
+        );
+    }
+
+    // The interval of an expression with some of its operators not chosen yet holds the
+    // value of every complete expression, so a branch that reaches a number is never cut.
+    #[test]
+    fn holds_every_value() {
+        let operators = [
+            Operator::CONCATENATION,
+            Operator::ADDITION,
+            Operator::SUBSTRACTION,
+            Operator::MULTIPLICATION,
+            Operator::POWER,
+            Operator::DIVISION,
+        ];
+        for operands in [
+            ["1", "2", "3", "4"],
+            ["0", "5", "2", "9"],
+            ["9", "8", "7", "6"],
+        ]
+        .iter()
+        {
+            for expression in expressions(operands, &operators) {
+                let value = match evaluate(&expression.join(" ")) {
+                    Ok(value) => value,
+                    Err(_) => continue,
+                };
+                let positions: Vec<usize> = (0..expression.len())
+                    .filter(|x| Operator::from_symbol(&expression[*x]).is_some())
+                    .collect();
+                for mask in 0..(1 << positions.len()) {
+                    let mut tokens: Vec<Option<&str>> =
+                        expression.iter().map(|x| Some(x.as_str())).collect();
+                    for (bit, position) in positions.iter().enumerate() {
+                        if mask & (1 << bit) != 0 {
+                            tokens[*position] = None;
+                        }
+                    }
+                    let interval = reachable(&tokens, &operators);
+                    assert!(
+                        interval.is_some_and(|x| x.contains(value)),
+                        "{} = {} is out of {:?} for {:?}",
+                        expression.join(" "),
+                        value,
+                        interval,
+                        tokens
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn misses_unreachable_numbers() {
+        let operators = [
+            Operator::ADDITION,
+            Operator::SUBSTRACTION,
+            Operator::MULTIPLICATION,
+        ];
+        let interval = reachable(&[Some("1"), Some("2"), None, Some("3"), None], &operators);
+        assert_eq!(
+            interval,
+            Some(Interval {
+                low: -4.0,
+                high: 9.0
+            })
+        );
+        assert!(!interval.unwrap().meets(9.5, 100.0));
+    }
+}
@@ -0,0 +1,10 @@
+// Shared by the search engines: operators, the RPN evaluator, its exact fractions, its
+// interval bounds and the residue analysis, the run configuration and the task queue
+// of the workers.
+pub mod configs_lib;
+pub mod intervals;
+pub mod operators;
+pub mod rational;
+pub mod residues;
+pub mod rpn_evaluator;
+pub mod tasks;
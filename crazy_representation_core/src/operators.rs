@@ -0,0 +1,1009 @@
+use std::convert::TryFrom;
+use std::fmt;
+use std::ops::Deref;
+
+use crate::rational::Rational;
+use crate::rpn_evaluator::{is_real_roots, EvalError};
+
+// largest degree of a root recognized in a float exponent
+const MAX_ROOT: i128 = 999;
+
+// How operands of the same precedence are grouped when the expression is written in infix
+// form: an operand with the precedence of the operator is parenthesized unless grouping it
+// this way gives the same value.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Associativity {
+    // (a op b) op c is a op (b op c), no parentheses
+    Full,
+    // a op b op c is (a op b) op c, the right operand is parenthesized
+    Left,
+    // a op b op c is a op (b op c), the left operand is parenthesized
+    Right,
+    // every operand is parenthesized
+    None,
+}
+
+// Everything the evaluators, the infix form and the search need to know about an operator.
+// An operator is added by implementing it and listing it in `REGISTRY`, the evaluators take
+// `arity` operands and the search uses binary operators only. Every evaluation checks
+// the operands against the domain of the operator, the evaluator then rejects a result
+// that is not finite.
+pub trait OperatorDef: Sync {
+    // name of the operator in the run configuration, "addition"
+    fn name(&self) -> &'static str;
+
+    // token of the operator in RPN expressions, "+"
+    fn symbol(&self) -> &'static str;
+
+    fn arity(&self) -> usize {
+        2
+    }
+
+    // operators of higher precedence are applied first in infix form
+    fn precedence(&self) -> u8;
+
+    fn associativity(&self) -> Associativity;
+
+    fn evaluate(&self, operands: &[f64], bounds: (f64, f64)) -> Result<f64, EvalError>;
+
+    fn evaluate_exact(
+        &self,
+        operands: &[Rational],
+        bounds: (f64, f64),
+    ) -> Result<Rational, EvalError>;
+
+    fn evaluate_integer(&self, operands: &[i128], bounds: (f64, f64)) -> Result<i128, EvalError>;
+
+    // Infix form of the operation over operands already parenthesized.
+    fn infix(&self, operands: &[String]) -> String {
+        operands.join(&format!(" {} ", self.symbol()))
+    }
+
+    // Whether the operator joins the digits of its operands, so that the search only
+    // applies it to operands pushed one after another unless results may be concatenated.
+    fn joins_digits(&self) -> bool {
+        false
+    }
+
+    // Whether the result may be a fraction, as the trees equal by associativity are only
+    // equal while every value is an integer.
+    fn may_round(&self) -> bool {
+        false
+    }
+
+    // Whether the operator belongs to the basic set of operations.
+    fn is_basic(&self) -> bool {
+        false
+    }
+
+    // Whether the operator belongs to the default set of operations, the others are only
+    // searched when they are asked for.
+    fn is_default(&self) -> bool {
+        true
+    }
+
+    // Whether a op (b right c) is (a op b) right c when `right` has the precedence of
+    // the operator, so that the infix form of a fully associative operator needs
+    // no parentheses around its right operand.
+    fn regroups_with(&self, _right: &Operator) -> bool {
+        true
+    }
+
+    // Whether the operator applied to a right operand computed by `right` is the form kept
+    // of the trees equal by associativity.
+    fn is_canonical_with(&self, _right: &Operator) -> bool {
+        true
+    }
+}
+
+// An operator of the registry, compared by its symbol.
+#[derive(Clone, Copy)]
+pub struct Operator(&'static dyn OperatorDef);
+
+impl Operator {
+    pub const CONCATENATION: Operator = Operator(&Concatenation);
+    pub const ADDITION: Operator = Operator(&Addition);
+    pub const SUBSTRACTION: Operator = Operator(&Substraction);
+    pub const MULTIPLICATION: Operator = Operator(&Multiplication);
+    pub const DIVISION: Operator = Operator(&Division);
+    pub const POWER: Operator = Operator(&Power);
+    pub const MODULO: Operator = Operator(&Modulo);
+    pub const INTEGER_DIVISION: Operator = Operator(&IntegerDivision);
+
+    pub fn from_symbol(symbol: &str) -> Option<Operator> {
+        REGISTRY.iter().find(|x| x.symbol() == symbol).copied()
+    }
+
+    pub fn from_name(name: &str) -> Option<Operator> {
+        REGISTRY.iter().find(|x| x.name() == name).copied()
+    }
+}
+
+// Every operator, in the order the search tries them.
+pub static REGISTRY: [Operator; 8] = [
+    Operator::CONCATENATION,
+    Operator::ADDITION,
+    Operator::MULTIPLICATION,
+    Operator::SUBSTRACTION,
+    Operator::POWER,
+    Operator::DIVISION,
+    Operator::MODULO,
+    Operator::INTEGER_DIVISION,
+];
+
+impl Deref for Operator {
+    type Target = dyn OperatorDef;
+
+    fn deref(&self) -> &Self::Target {
+        self.0
+    }
+}
+
+impl PartialEq for Operator {
+    fn eq(&self, other: &Operator) -> bool {
+        self.symbol() == other.symbol()
+    }
+}
+
+impl fmt::Debug for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+fn pair<T: Copy>(operands: &[T]) -> (T, T) {
+    (operands[0], operands[1])
+}
+
+struct Concatenation;
+
+// _ means 'concatenations' here
+impl OperatorDef for Concatenation {
+    fn name(&self) -> &'static str {
+        "concatenation"
+    }
+
+    fn symbol(&self) -> &'static str {
+        "_"
+    }
+
+    fn precedence(&self) -> u8 {
+        4
+    }
+
+    fn associativity(&self) -> Associativity {
+        Associativity::Full
+    }
+
+    fn evaluate(&self, operands: &[f64], _bounds: (f64, f64)) -> Result<f64, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        // only digits of non-negative integers can be written one after another
+        if operand1 < 0.0 || operand2 < 0.0 || operand1.fract() != 0.0 || operand2.fract() != 0.0 {
+            return Err(EvalError::UnrealConcatenation);
+        }
+        // let s_o = operand2.to_string();
+        // operand1 * 10.0_f64.powi(s_o.len() as i32) + operand2
+        // + 0.0 writes a negative zero as 0, not as -0
+        format!("{}{}", operand1 + 0.0, operand2 + 0.0)
+            .parse::<f64>()
+            .map_err(|_| EvalError::UnrealConcatenation)
+    }
+
+    fn evaluate_exact(
+        &self,
+        operands: &[Rational],
+        _bounds: (f64, f64),
+    ) -> Result<Rational, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        match (operand1.to_integer(), operand2.to_integer()) {
+            (Some(x), Some(y)) if x >= 0 && y >= 0 => format!("{}{}", x, y)
+                .parse::<i128>()
+                .map(Rational::integer)
+                .map_err(|_| EvalError::Overflow(Operator::CONCATENATION)),
+            _ => Err(EvalError::UnrealConcatenation),
+        }
+    }
+
+    fn evaluate_integer(&self, operands: &[i128], _bounds: (f64, f64)) -> Result<i128, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        if operand1 < 0 || operand2 < 0 {
+            return Err(EvalError::UnrealConcatenation);
+        }
+        format!("{}{}", operand1, operand2)
+            .parse::<i128>()
+            .map_err(|_| EvalError::Overflow(Operator::CONCATENATION))
+    }
+
+    fn infix(&self, operands: &[String]) -> String {
+        operands.concat()
+    }
+
+    fn joins_digits(&self) -> bool {
+        true
+    }
+
+    fn is_basic(&self) -> bool {
+        true
+    }
+}
+
+struct Addition;
+
+impl OperatorDef for Addition {
+    fn name(&self) -> &'static str {
+        "addition"
+    }
+
+    fn symbol(&self) -> &'static str {
+        "+"
+    }
+
+    fn precedence(&self) -> u8 {
+        1
+    }
+
+    fn associativity(&self) -> Associativity {
+        Associativity::Full
+    }
+
+    fn evaluate(&self, operands: &[f64], _bounds: (f64, f64)) -> Result<f64, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        Ok(operand1 + operand2)
+    }
+
+    fn evaluate_exact(
+        &self,
+        operands: &[Rational],
+        _bounds: (f64, f64),
+    ) -> Result<Rational, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        operand1
+            .checked_add(&operand2)
+            .ok_or(EvalError::Overflow(Operator::ADDITION))
+    }
+
+    fn evaluate_integer(&self, operands: &[i128], _bounds: (f64, f64)) -> Result<i128, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        operand1
+            .checked_add(operand2)
+            .ok_or(EvalError::Overflow(Operator::ADDITION))
+    }
+
+    fn is_basic(&self) -> bool {
+        true
+    }
+
+    // a + (b + c) is searched as (a + b) + c and a + (b - c) as (a + b) - c
+    fn is_canonical_with(&self, right: &Operator) -> bool {
+        !matches!(right.symbol(), "+" | "-")
+    }
+}
+
+struct Substraction;
+
+impl OperatorDef for Substraction {
+    fn name(&self) -> &'static str {
+        "substraction"
+    }
+
+    fn symbol(&self) -> &'static str {
+        "-"
+    }
+
+    fn precedence(&self) -> u8 {
+        1
+    }
+
+    fn associativity(&self) -> Associativity {
+        Associativity::Left
+    }
+
+    fn evaluate(&self, operands: &[f64], _bounds: (f64, f64)) -> Result<f64, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        Ok(operand1 - operand2)
+    }
+
+    fn evaluate_exact(
+        &self,
+        operands: &[Rational],
+        _bounds: (f64, f64),
+    ) -> Result<Rational, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        operand1
+            .checked_sub(&operand2)
+            .ok_or(EvalError::Overflow(Operator::SUBSTRACTION))
+    }
+
+    fn evaluate_integer(&self, operands: &[i128], _bounds: (f64, f64)) -> Result<i128, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        operand1
+            .checked_sub(operand2)
+            .ok_or(EvalError::Overflow(Operator::SUBSTRACTION))
+    }
+
+    fn is_basic(&self) -> bool {
+        true
+    }
+
+    // a - (b - c) is searched as (a - b) + c and a - (b + c) as (a - b) - c
+    fn is_canonical_with(&self, right: &Operator) -> bool {
+        !matches!(right.symbol(), "+" | "-")
+    }
+}
+
+struct Multiplication;
+
+impl OperatorDef for Multiplication {
+    fn name(&self) -> &'static str {
+        "multiplication"
+    }
+
+    fn symbol(&self) -> &'static str {
+        "*"
+    }
+
+    fn precedence(&self) -> u8 {
+        2
+    }
+
+    fn associativity(&self) -> Associativity {
+        Associativity::Full
+    }
+
+    fn evaluate(&self, operands: &[f64], _bounds: (f64, f64)) -> Result<f64, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        Ok(operand1 * operand2)
+    }
+
+    fn evaluate_exact(
+        &self,
+        operands: &[Rational],
+        _bounds: (f64, f64),
+    ) -> Result<Rational, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        operand1
+            .checked_mul(&operand2)
+            .ok_or(EvalError::Overflow(Operator::MULTIPLICATION))
+    }
+
+    fn evaluate_integer(&self, operands: &[i128], _bounds: (f64, f64)) -> Result<i128, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        operand1
+            .checked_mul(operand2)
+            .ok_or(EvalError::Overflow(Operator::MULTIPLICATION))
+    }
+
+    fn is_basic(&self) -> bool {
+        true
+    }
+
+    // a * (b * c) is searched as (a * b) * c
+    fn is_canonical_with(&self, right: &Operator) -> bool {
+        right.symbol() != "*"
+    }
+
+    // a * (b / c) is a * b / c, but a * (b % c) is not a * b % c
+    fn regroups_with(&self, right: &Operator) -> bool {
+        matches!(right.symbol(), "*" | "/")
+    }
+}
+
+struct Division;
+
+// The result is bounded by both bounds.
+impl OperatorDef for Division {
+    fn name(&self) -> &'static str {
+        "division"
+    }
+
+    fn symbol(&self) -> &'static str {
+        "/"
+    }
+
+    fn precedence(&self) -> u8 {
+        2
+    }
+
+    fn associativity(&self) -> Associativity {
+        Associativity::None
+    }
+
+    fn evaluate(&self, operands: &[f64], bounds: (f64, f64)) -> Result<f64, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        let (lower_bound, upper_bound) = bounds;
+        if operand2 == 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        let op_result = operand1 / operand2;
+        if op_result.is_finite() && !(lower_bound..=upper_bound).contains(&op_result) {
+            return Err(EvalError::OutOfBounds(Operator::DIVISION));
+        }
+        Ok(op_result)
+    }
+
+    fn evaluate_exact(
+        &self,
+        operands: &[Rational],
+        bounds: (f64, f64),
+    ) -> Result<Rational, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        let (lower_bound, upper_bound) = bounds;
+        if operand2 == Rational::ZERO {
+            return Err(EvalError::DivisionByZero);
+        }
+        let op_result = operand1
+            .checked_div(&operand2)
+            .ok_or(EvalError::Overflow(Operator::DIVISION))?;
+        if !(lower_bound..=upper_bound).contains(&op_result.to_f64()) {
+            Err(EvalError::OutOfBounds(Operator::DIVISION))
+        } else {
+            Ok(op_result)
+        }
+    }
+
+    fn evaluate_integer(&self, operands: &[i128], bounds: (f64, f64)) -> Result<i128, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        let (lower_bound, upper_bound) = bounds;
+        if operand2 == 0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        if operand1 % operand2 != 0 {
+            return Err(EvalError::InexactDivision);
+        }
+        let op_result = operand1
+            .checked_div(operand2)
+            .ok_or(EvalError::Overflow(Operator::DIVISION))?;
+        if !(lower_bound..=upper_bound).contains(&(op_result as f64)) {
+            Err(EvalError::OutOfBounds(Operator::DIVISION))
+        } else {
+            Ok(op_result)
+        }
+    }
+
+    fn may_round(&self) -> bool {
+        true
+    }
+}
+
+struct Power;
+
+// The result is at least the lower bound and below the upper bound.
+impl OperatorDef for Power {
+    fn name(&self) -> &'static str {
+        "power"
+    }
+
+    fn symbol(&self) -> &'static str {
+        "^"
+    }
+
+    fn precedence(&self) -> u8 {
+        3
+    }
+
+    fn associativity(&self) -> Associativity {
+        Associativity::Right
+    }
+
+    fn evaluate(&self, operands: &[f64], bounds: (f64, f64)) -> Result<f64, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        let (lower_bound, upper_bound) = bounds;
+        if operand1 == 0.0 && operand2 < 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        let op_result = if operand1 < 0.0 && operand2.fract() != 0.0 {
+            let numerator = match is_real_roots() {
+                true => odd_root_numerator(operand2),
+                false => None,
+            }
+            .ok_or(EvalError::NegativeBase)?;
+            // the real root of an odd degree has the sign of the base
+            let power = (-operand1).powf(operand2);
+            match numerator % 2.0 == 0.0 {
+                true => power,
+                false => -power,
+            }
+        } else {
+            operand1.powf(operand2)
+        };
+        if op_result.is_finite() && !(lower_bound..upper_bound).contains(&op_result) {
+            return Err(EvalError::OutOfBounds(Operator::POWER));
+        }
+        Ok(op_result)
+    }
+
+    fn evaluate_exact(
+        &self,
+        operands: &[Rational],
+        bounds: (f64, f64),
+    ) -> Result<Rational, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        let (lower_bound, upper_bound) = bounds;
+        let op_result = operand1.checked_pow(&operand2)?;
+        if !(lower_bound..upper_bound).contains(&op_result.to_f64()) {
+            Err(EvalError::OutOfBounds(Operator::POWER))
+        } else {
+            Ok(op_result)
+        }
+    }
+
+    fn evaluate_integer(&self, operands: &[i128], bounds: (f64, f64)) -> Result<i128, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        let (lower_bound, upper_bound) = bounds;
+        let exponent = u32::try_from(operand2).map_err(|_| EvalError::NegativeExponent)?;
+        let op_result = operand1
+            .checked_pow(exponent)
+            .ok_or(EvalError::Overflow(Operator::POWER))?;
+        if !(lower_bound..upper_bound).contains(&(op_result as f64)) {
+            Err(EvalError::OutOfBounds(Operator::POWER))
+        } else {
+            Ok(op_result)
+        }
+    }
+
+    fn may_round(&self) -> bool {
+        true
+    }
+}
+
+// Numerator p of the exponent when it is p/q with an odd q, `None` for an even q. The float
+// only approximates p/q, the exact exponent is read back from it as `checked_pow` has it.
+fn odd_root_numerator(exponent: f64) -> Option<f64> {
+    Rational::from_f64(exponent, MAX_ROOT)
+        .filter(|exponent| exponent.denominator() % 2 == 1)
+        .map(|exponent| exponent.numerator() as f64)
+}
+
+struct Modulo;
+
+// The remainder of the integer division rounded down: it has the sign of the divisor,
+// so 7 % -2 is -1 and -7 % 2 is 1. Operands may be fractions, 7/2 % 1 is 1/2.
+impl OperatorDef for Modulo {
+    fn name(&self) -> &'static str {
+        "modulo"
+    }
+
+    fn symbol(&self) -> &'static str {
+        "%"
+    }
+
+    fn precedence(&self) -> u8 {
+        2
+    }
+
+    fn associativity(&self) -> Associativity {
+        Associativity::Left
+    }
+
+    fn evaluate(&self, operands: &[f64], _bounds: (f64, f64)) -> Result<f64, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        if operand2 == 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        Ok(floor_remainder(operand1, operand2))
+    }
+
+    fn evaluate_exact(
+        &self,
+        operands: &[Rational],
+        _bounds: (f64, f64),
+    ) -> Result<Rational, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        let overflow = || EvalError::Overflow(Operator::MODULO);
+        let quotient = floor_quotient(&operand1, &operand2, Operator::MODULO)?;
+        operand2
+            .checked_mul(&quotient)
+            .and_then(|x| operand1.checked_sub(&x))
+            .ok_or_else(overflow)
+    }
+
+    fn evaluate_integer(&self, operands: &[i128], _bounds: (f64, f64)) -> Result<i128, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        if operand2 == 0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        let remainder = operand1
+            .checked_rem(operand2)
+            .ok_or(EvalError::Overflow(Operator::MODULO))?;
+        match remainder != 0 && (remainder < 0) != (operand2 < 0) {
+            true => Ok(remainder + operand2),
+            false => Ok(remainder),
+        }
+    }
+
+    fn is_default(&self) -> bool {
+        false
+    }
+}
+
+struct IntegerDivision;
+
+// The quotient rounded down, so 7 // -2 is -4 and -7 // 2 is -4: a is always
+// (a // b) * b + a % b.
+impl OperatorDef for IntegerDivision {
+    fn name(&self) -> &'static str {
+        "integer-division"
+    }
+
+    fn symbol(&self) -> &'static str {
+        "//"
+    }
+
+    fn precedence(&self) -> u8 {
+        2
+    }
+
+    fn associativity(&self) -> Associativity {
+        Associativity::Left
+    }
+
+    fn evaluate(&self, operands: &[f64], _bounds: (f64, f64)) -> Result<f64, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        if operand2 == 0.0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        // the remainder is exact, so is the quotient of what is left
+        Ok(((operand1 - floor_remainder(operand1, operand2)) / operand2).round())
+    }
+
+    fn evaluate_exact(
+        &self,
+        operands: &[Rational],
+        _bounds: (f64, f64),
+    ) -> Result<Rational, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        floor_quotient(&operand1, &operand2, Operator::INTEGER_DIVISION)
+    }
+
+    fn evaluate_integer(&self, operands: &[i128], _bounds: (f64, f64)) -> Result<i128, EvalError> {
+        let (operand1, operand2) = pair(operands);
+        if operand2 == 0 {
+            return Err(EvalError::DivisionByZero);
+        }
+        let overflow = || EvalError::Overflow(Operator::INTEGER_DIVISION);
+        let quotient = operand1.checked_div(operand2).ok_or_else(overflow)?;
+        match operand1 % operand2 != 0 && (operand1 < 0) != (operand2 < 0) {
+            true => Ok(quotient - 1),
+            false => Ok(quotient),
+        }
+    }
+
+    fn is_default(&self) -> bool {
+        false
+    }
+}
+
+// Remainder of the float division rounded down, with the sign of the divisor.
+fn floor_remainder(operand1: f64, operand2: f64) -> f64 {
+    let remainder = operand1 % operand2;
+    match remainder != 0.0 && (remainder < 0.0) != (operand2 < 0.0) {
+        true => remainder + operand2,
+        false => remainder,
+    }
+}
+
+// Quotient of the exact division rounded down.
+fn floor_quotient(
+    operand1: &Rational,
+    operand2: &Rational,
+    operator: Operator,
+) -> Result<Rational, EvalError> {
+    if *operand2 == Rational::ZERO {
+        return Err(EvalError::DivisionByZero);
+    }
+    operand1
+        .checked_div(operand2)
+        .map(|x| Rational::integer(x.floor()))
+        .ok_or(EvalError::Overflow(operator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rpn_evaluator::{apply, evaluate_exact};
+
+    const BOUNDS: (f64, f64) = (0.001, 1073741824.0);
+
+    fn float(operator: Operator, operand1: f64, operand2: f64) -> Result<f64, EvalError> {
+        apply(&operator, &[operand1, operand2], BOUNDS)
+    }
+
+    fn integer(operator: Operator, operand1: i128, operand2: i128) -> Result<i128, EvalError> {
+        operator.evaluate_integer(&[operand1, operand2], BOUNDS)
+    }
+
+    fn exact(operator: Operator, operand1: &str, operand2: &str) -> Result<Rational, EvalError> {
+        operator.evaluate_exact(
+            &[operand1.parse().unwrap(), operand2.parse().unwrap()],
+            BOUNDS,
+        )
+    }
+
+    fn rational(value: &str) -> Result<Rational, EvalError> {
+        Ok(value.parse().unwrap())
+    }
+
+    #[test]
+    fn addition() {
+        assert_eq!(float(Operator::ADDITION, 2.0, 3.0), Ok(5.0));
+        assert_eq!(
+            float(Operator::ADDITION, f64::MAX, f64::MAX),
+            Err(EvalError::Overflow(Operator::ADDITION))
+        );
+        assert_eq!(integer(Operator::ADDITION, -2, 3), Ok(1));
+        assert_eq!(
+            integer(Operator::ADDITION, i128::MAX, 1),
+            Err(EvalError::Overflow(Operator::ADDITION))
+        );
+        assert_eq!(exact(Operator::ADDITION, "1/3", "1/6"), rational("1/2"));
+        assert_eq!(
+            exact(
+                Operator::ADDITION,
+                "170141183460469231731687303715884105727",
+                "1"
+            ),
+            Err(EvalError::Overflow(Operator::ADDITION))
+        );
+    }
+
+    #[test]
+    fn substraction() {
+        assert_eq!(float(Operator::SUBSTRACTION, 2.0, 3.0), Ok(-1.0));
+        assert_eq!(
+            float(Operator::SUBSTRACTION, -f64::MAX, f64::MAX),
+            Err(EvalError::Overflow(Operator::SUBSTRACTION))
+        );
+        assert_eq!(integer(Operator::SUBSTRACTION, 2, 3), Ok(-1));
+        assert_eq!(
+            integer(Operator::SUBSTRACTION, i128::MIN, 1),
+            Err(EvalError::Overflow(Operator::SUBSTRACTION))
+        );
+        assert_eq!(exact(Operator::SUBSTRACTION, "1/2", "1/3"), rational("1/6"));
+        assert_eq!(
+            exact(
+                Operator::SUBSTRACTION,
+                "-170141183460469231731687303715884105727",
+                "2"
+            ),
+            Err(EvalError::Overflow(Operator::SUBSTRACTION))
+        );
+    }
+
+    #[test]
+    fn multiplication() {
+        assert_eq!(float(Operator::MULTIPLICATION, 4.0, -2.5), Ok(-10.0));
+        assert_eq!(
+            float(Operator::MULTIPLICATION, 1e200, 1e200),
+            Err(EvalError::Overflow(Operator::MULTIPLICATION))
+        );
+        assert_eq!(integer(Operator::MULTIPLICATION, -4, 3), Ok(-12));
+        assert_eq!(
+            integer(Operator::MULTIPLICATION, i128::MAX, 2),
+            Err(EvalError::Overflow(Operator::MULTIPLICATION))
+        );
+        assert_eq!(
+            exact(Operator::MULTIPLICATION, "2/3", "9/4"),
+            rational("3/2")
+        );
+        assert_eq!(
+            exact(Operator::MULTIPLICATION, "2^100", "2^100"),
+            Err(EvalError::Overflow(Operator::MULTIPLICATION))
+        );
+    }
+
+    #[test]
+    fn division() {
+        assert_eq!(float(Operator::DIVISION, 7.0, 2.0), Ok(3.5));
+        assert_eq!(
+            float(Operator::DIVISION, 1.0, 0.0),
+            Err(EvalError::DivisionByZero)
+        );
+        assert_eq!(
+            float(Operator::DIVISION, 0.0, 0.0),
+            Err(EvalError::DivisionByZero)
+        );
+        assert_eq!(
+            float(Operator::DIVISION, 1.0, -0.0),
+            Err(EvalError::DivisionByZero)
+        );
+        assert_eq!(
+            float(Operator::DIVISION, 1.0, 10000.0),
+            Err(EvalError::OutOfBounds(Operator::DIVISION))
+        );
+        assert_eq!(
+            float(Operator::DIVISION, f64::MAX, 0.5),
+            Err(EvalError::Overflow(Operator::DIVISION))
+        );
+        assert_eq!(integer(Operator::DIVISION, 8, 2), Ok(4));
+        assert_eq!(
+            integer(Operator::DIVISION, 1, 0),
+            Err(EvalError::DivisionByZero)
+        );
+        assert_eq!(
+            integer(Operator::DIVISION, 7, 2),
+            Err(EvalError::InexactDivision)
+        );
+        assert_eq!(
+            integer(Operator::DIVISION, -8, 2),
+            Err(EvalError::OutOfBounds(Operator::DIVISION))
+        );
+        assert_eq!(exact(Operator::DIVISION, "22", "7"), rational("22/7"));
+        assert_eq!(
+            exact(Operator::DIVISION, "1", "0"),
+            Err(EvalError::DivisionByZero)
+        );
+        assert_eq!(
+            exact(Operator::DIVISION, "1", "10000"),
+            Err(EvalError::OutOfBounds(Operator::DIVISION))
+        );
+    }
+
+    #[test]
+    fn concatenation() {
+        assert_eq!(float(Operator::CONCATENATION, 12.0, 3.0), Ok(123.0));
+        assert_eq!(float(Operator::CONCATENATION, 1.0, 0.0), Ok(10.0));
+        assert_eq!(float(Operator::CONCATENATION, -0.0, 5.0), Ok(5.0));
+        assert_eq!(
+            float(Operator::CONCATENATION, -1.0, 2.0),
+            Err(EvalError::UnrealConcatenation)
+        );
+        assert_eq!(
+            float(Operator::CONCATENATION, 1.5, 2.0),
+            Err(EvalError::UnrealConcatenation)
+        );
+        assert_eq!(
+            float(Operator::CONCATENATION, 1e300, 1e300),
+            Err(EvalError::Overflow(Operator::CONCATENATION))
+        );
+        assert_eq!(integer(Operator::CONCATENATION, 12, 3), Ok(123));
+        assert_eq!(
+            integer(Operator::CONCATENATION, 2, -3),
+            Err(EvalError::UnrealConcatenation)
+        );
+        assert_eq!(
+            integer(Operator::CONCATENATION, i128::MAX, 1),
+            Err(EvalError::Overflow(Operator::CONCATENATION))
+        );
+        assert_eq!(exact(Operator::CONCATENATION, "12", "3"), rational("123"));
+        assert_eq!(
+            exact(Operator::CONCATENATION, "1/2", "3"),
+            Err(EvalError::UnrealConcatenation)
+        );
+    }
+
+    #[test]
+    fn power() {
+        assert_eq!(float(Operator::POWER, 2.0, 10.0), Ok(1024.0));
+        assert_eq!(float(Operator::POWER, -2.0, 2.0), Ok(4.0));
+        assert_eq!(float(Operator::POWER, 4.0, 0.5), Ok(2.0));
+        assert_eq!(
+            float(Operator::POWER, 0.0, -1.0),
+            Err(EvalError::DivisionByZero)
+        );
+        assert_eq!(
+            float(Operator::POWER, -2.0, 0.5),
+            Err(EvalError::NegativeBase)
+        );
+        assert_eq!(
+            float(Operator::POWER, 2.0, 40.0),
+            Err(EvalError::OutOfBounds(Operator::POWER))
+        );
+        assert_eq!(
+            float(Operator::POWER, 10.0, 400.0),
+            Err(EvalError::Overflow(Operator::POWER))
+        );
+        assert_eq!(integer(Operator::POWER, 3, 4), Ok(81));
+        assert_eq!(
+            integer(Operator::POWER, 2, -1),
+            Err(EvalError::NegativeExponent)
+        );
+        assert_eq!(
+            integer(Operator::POWER, 10, 40),
+            Err(EvalError::Overflow(Operator::POWER))
+        );
+        assert_eq!(exact(Operator::POWER, "4", "3/2"), rational("8"));
+        assert_eq!(
+            exact(Operator::POWER, "2", "1/2"),
+            Err(EvalError::Irrational)
+        );
+        assert_eq!(
+            exact(Operator::POWER, "-8", "1/3"),
+            Err(EvalError::NegativeBase)
+        );
+        assert_eq!(
+            exact(Operator::POWER, "0", "-1"),
+            Err(EvalError::DivisionByZero)
+        );
+        assert_eq!(
+            exact(Operator::POWER, "10", "40"),
+            Err(EvalError::Overflow(Operator::POWER))
+        );
+    }
+
+    #[test]
+    fn modulo() {
+        assert_eq!(float(Operator::MODULO, 7.0, 2.0), Ok(1.0));
+        assert_eq!(float(Operator::MODULO, -7.0, 2.0), Ok(1.0));
+        assert_eq!(float(Operator::MODULO, 7.0, -2.0), Ok(-1.0));
+        assert_eq!(float(Operator::MODULO, 3.5, 1.0), Ok(0.5));
+        assert_eq!(
+            float(Operator::MODULO, 7.0, 0.0),
+            Err(EvalError::DivisionByZero)
+        );
+        assert_eq!(integer(Operator::MODULO, -7, 2), Ok(1));
+        assert_eq!(integer(Operator::MODULO, 7, -2), Ok(-1));
+        assert_eq!(integer(Operator::MODULO, -8, 2), Ok(0));
+        assert_eq!(
+            integer(Operator::MODULO, 7, 0),
+            Err(EvalError::DivisionByZero)
+        );
+        assert_eq!(
+            integer(Operator::MODULO, i128::MIN, -1),
+            Err(EvalError::Overflow(Operator::MODULO))
+        );
+        assert_eq!(exact(Operator::MODULO, "-7", "2"), rational("1"));
+        assert_eq!(exact(Operator::MODULO, "7/2", "1"), rational("1/2"));
+        assert_eq!(exact(Operator::MODULO, "7", "-2"), rational("-1"));
+        assert_eq!(
+            exact(Operator::MODULO, "7", "0"),
+            Err(EvalError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn integer_division() {
+        assert_eq!(float(Operator::INTEGER_DIVISION, 7.0, 2.0), Ok(3.0));
+        assert_eq!(float(Operator::INTEGER_DIVISION, -7.0, 2.0), Ok(-4.0));
+        assert_eq!(float(Operator::INTEGER_DIVISION, 7.0, -2.0), Ok(-4.0));
+        assert_eq!(
+            float(Operator::INTEGER_DIVISION, 7.0, 0.0),
+            Err(EvalError::DivisionByZero)
+        );
+        assert_eq!(
+            float(Operator::INTEGER_DIVISION, f64::MAX, 0.5),
+            Err(EvalError::Overflow(Operator::INTEGER_DIVISION))
+        );
+        assert_eq!(integer(Operator::INTEGER_DIVISION, -7, 2), Ok(-4));
+        assert_eq!(integer(Operator::INTEGER_DIVISION, -8, 2), Ok(-4));
+        assert_eq!(integer(Operator::INTEGER_DIVISION, 7, -2), Ok(-4));
+        assert_eq!(
+            integer(Operator::INTEGER_DIVISION, 7, 0),
+            Err(EvalError::DivisionByZero)
+        );
+        assert_eq!(
+            integer(Operator::INTEGER_DIVISION, i128::MIN, -1),
+            Err(EvalError::Overflow(Operator::INTEGER_DIVISION))
+        );
+        assert_eq!(exact(Operator::INTEGER_DIVISION, "-7", "2"), rational("-4"));
+        assert_eq!(
+            exact(Operator::INTEGER_DIVISION, "22/7", "1/2"),
+            rational("6")
+        );
+        assert_eq!(
+            exact(Operator::INTEGER_DIVISION, "1", "0"),
+            Err(EvalError::DivisionByZero)
+        );
+    }
+
+    #[test]
+    fn canonical_trees() {
+        // every tree that is not canonical is equal to the canonical one
+        let equal = [
+            ("7 3 2 + +", "7 3 + 2 +"),
+            ("7 3 2 - +", "7 3 + 2 -"),
+            ("7 3 2 - -", "7 3 - 2 +"),
+            ("7 3 2 + -", "7 3 - 2 -"),
+            ("7 3 2 * *", "7 3 * 2 *"),
+        ];
+        for (skipped, kept) in equal.iter() {
+            assert_eq!(evaluate_exact(skipped), evaluate_exact(kept));
+        }
+        assert!(!Operator::ADDITION.is_canonical_with(&Operator::SUBSTRACTION));
+        assert!(!Operator::SUBSTRACTION.is_canonical_with(&Operator::ADDITION));
+        assert!(!Operator::MULTIPLICATION.is_canonical_with(&Operator::MULTIPLICATION));
+        assert!(Operator::ADDITION.is_canonical_with(&Operator::MULTIPLICATION));
+        assert!(Operator::MULTIPLICATION.is_canonical_with(&Operator::DIVISION));
+        assert!(Operator::DIVISION.is_canonical_with(&Operator::DIVISION));
+        assert!(Operator::POWER.is_canonical_with(&Operator::POWER));
+    }
+}
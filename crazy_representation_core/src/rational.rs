@@ -0,0 +1,509 @@
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::operators::Operator;
+use crate::rpn_evaluator::{evaluate_exact, is_real_roots, EvalError};
+
+// Exact fraction in lowest terms with a positive denominator, the type of the targets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    numerator: i128,
+    denominator: i128,
+}
+
+impl Rational {
+    pub const ZERO: Rational = Rational {
+        numerator: 0,
+        denominator: 1,
+    };
+
+    // `None` for a zero denominator or when the fraction does not fit once reduced.
+    pub fn new(numerator: i128, denominator: i128) -> Option<Rational> {
+        if denominator == 0 {
+            return None;
+        }
+        let divisor = gcd(numerator, denominator);
+        let (numerator, denominator) = (numerator / divisor, denominator / divisor);
+        match denominator < 0 {
+            true => Some(Rational {
+                numerator: numerator.checked_neg()?,
+                denominator: denominator.checked_neg()?,
+            }),
+            false => Some(Rational {
+                numerator,
+                denominator,
+            }),
+        }
+    }
+
+    pub fn integer(value: i128) -> Rational {
+        Rational {
+            numerator: value,
+            denominator: 1,
+        }
+    }
+
+    // The fraction with a denominator up to `max_denominator` the float is a rounding of,
+    // a convergent of its continued fraction: 1/3 from 0.333.., `None` for 2^0.5.
+    pub fn from_f64(value: f64, max_denominator: i128) -> Option<Rational> {
+        let tolerance = 1e-12 * value.abs().max(1.0);
+        let (mut previous, mut convergent) = ((0i128, 1i128), (1i128, 0i128));
+        let mut rest = value;
+        loop {
+            if !rest.is_finite() || rest.abs() >= i64::MAX as f64 {
+                return None;
+            }
+            let term = rest.floor();
+            let (numerator, denominator) = (
+                (term as i128)
+                    .checked_mul(convergent.0)?
+                    .checked_add(previous.0)?,
+                (term as i128)
+                    .checked_mul(convergent.1)?
+                    .checked_add(previous.1)?,
+            );
+            if denominator > max_denominator {
+                return None;
+            }
+            if (numerator as f64 / denominator as f64 - value).abs() <= tolerance {
+                return Rational::new(numerator, denominator);
+            }
+            previous = convergent;
+            convergent = (numerator, denominator);
+            rest = 1.0 / (rest - term);
+        }
+    }
+
+    pub fn numerator(&self) -> i128 {
+        self.numerator
+    }
+
+    pub fn denominator(&self) -> i128 {
+        self.denominator
+    }
+
+    pub fn to_integer(&self) -> Option<i128> {
+        match self.denominator == 1 {
+            true => Some(self.numerator),
+            false => None,
+        }
+    }
+
+    // Greatest integer not above the number.
+    pub fn floor(&self) -> i128 {
+        self.numerator.div_euclid(self.denominator)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.numerator < 0
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.numerator as f64 / self.denominator as f64
+    }
+
+    pub fn checked_add(&self, other: &Rational) -> Option<Rational> {
+        let divisor = gcd(self.denominator, other.denominator);
+        let denominator = (self.denominator / divisor).checked_mul(other.denominator)?;
+        let numerator = self
+            .numerator
+            .checked_mul(denominator / self.denominator)?
+            .checked_add(
+                other
+                    .numerator
+                    .checked_mul(denominator / other.denominator)?,
+            )?;
+        Rational::new(numerator, denominator)
+    }
+
+    pub fn checked_sub(&self, other: &Rational) -> Option<Rational> {
+        self.checked_add(&Rational {
+            numerator: other.numerator.checked_neg()?,
+            denominator: other.denominator,
+        })
+    }
+
+    pub fn checked_mul(&self, other: &Rational) -> Option<Rational> {
+        // cross reduction first, so the products stay as small as possible
+        let first = gcd(self.numerator, other.denominator);
+        let second = gcd(other.numerator, self.denominator);
+        Rational::new(
+            (self.numerator / first).checked_mul(other.numerator / second)?,
+            (self.denominator / second).checked_mul(other.denominator / first)?,
+        )
+    }
+
+    // `None` for a zero divisor too.
+    pub fn checked_div(&self, other: &Rational) -> Option<Rational> {
+        self.checked_mul(&Rational::new(other.denominator, other.numerator)?)
+    }
+
+    // Integer powers, and roots when both terms of the base are exact powers: 4^(3/2) is 8,
+    // but 2^(1/2) is irrational. A negative base under a fractional exponent fails, unless
+    // real roots are set and the root is of an odd degree: (-8)^(2/3) is 4.
+    pub fn checked_pow(&self, exponent: &Rational) -> Result<Rational, EvalError> {
+        let overflow = || EvalError::Overflow(Operator::POWER);
+        let base = match exponent.denominator {
+            1 => *self,
+            root => match (self.is_negative(), u32::try_from(root)) {
+                (false, Ok(root)) => Rational {
+                    numerator: integer_root(self.numerator, root).ok_or(EvalError::Irrational)?,
+                    denominator: integer_root(self.denominator, root)
+                        .ok_or(EvalError::Irrational)?,
+                },
+                (true, Ok(root)) if root % 2 == 1 && is_real_roots() => Rational {
+                    numerator: -integer_root(
+                        self.numerator.checked_neg().ok_or_else(overflow)?,
+                        root,
+                    )
+                    .ok_or(EvalError::Irrational)?,
+                    denominator: integer_root(self.denominator, root)
+                        .ok_or(EvalError::Irrational)?,
+                },
+                (true, _) => return Err(EvalError::NegativeBase),
+                _ => return Err(EvalError::Irrational),
+            },
+        };
+        let power = u32::try_from(exponent.numerator.unsigned_abs()).map_err(|_| overflow())?;
+        let raised = Rational::new(
+            base.numerator.checked_pow(power).ok_or_else(overflow)?,
+            base.denominator.checked_pow(power).ok_or_else(overflow)?,
+        )
+        .ok_or_else(overflow)?;
+        match exponent.is_negative() {
+            true => Rational::integer(1)
+                .checked_div(&raised)
+                .ok_or(EvalError::DivisionByZero),
+            false => Ok(raised),
+        }
+    }
+
+    // Float values closer than this to the number are compared exactly.
+    fn tolerance(&self) -> f64 {
+        1e-9 * self.to_f64().abs().max(1.0)
+    }
+
+    // Whether the float value of an expression is close enough to the number
+    // to be compared exactly.
+    pub fn is_near(&self, calculated: f64) -> bool {
+        (calculated - self.to_f64()).abs() <= self.tolerance()
+    }
+
+    // Range of float values an expression equal to the number may be evaluated to.
+    pub fn float_range(&self) -> (f64, f64) {
+        let value = self.to_f64();
+        (value - self.tolerance(), value + self.tolerance())
+    }
+
+    // Whether the expression, whose float value is `calculated`, is equal to the number.
+    // Floats only pick the candidates: a close enough expression is evaluated exactly, and
+    // compared as a float when one of its values is irrational or overflows.
+    pub fn matches(&self, expr: &str, calculated: f64) -> bool {
+        if !self.is_near(calculated) {
+            return false;
+        }
+        match evaluate_exact(expr) {
+            Ok(exact) => exact == *self,
+            Err(_) => calculated == self.to_f64(),
+        }
+    }
+}
+
+fn gcd(a: i128, b: i128) -> i128 {
+    let (mut a, mut b) = (a.unsigned_abs(), b.unsigned_abs());
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    // only i128::MIN alone has a greatest divisor out of range, it is kept unreduced
+    i128::try_from(a).unwrap_or(1).max(1)
+}
+
+// Non-negative integer whose `root`-th power is the value, `None` when there is none.
+// The root is found by bisection: the float root of a large value can be off by more than 1.
+fn integer_root(value: i128, root: u32) -> Option<i128> {
+    if value < 0 || root == 0 {
+        return None;
+    }
+    if root == 1 {
+        return Some(value);
+    }
+    // low^root <= value < high^root, the powers below 2^127 have roots below 2^(127/root + 1)
+    let (mut low, mut high) = (0_i128, 1_i128 << (127 / root + 1));
+    while high - low > 1 {
+        let middle = low + (high - low) / 2;
+        match middle.checked_pow(root) {
+            Some(power) if power <= value => low = middle,
+            _ => high = middle,
+        }
+    }
+    match low.checked_pow(root) == Some(value) {
+        true => Some(low),
+        false => None,
+    }
+}
+
+impl From<u64> for Rational {
+    fn from(value: u64) -> Rational {
+        Rational::integer(value as i128)
+    }
+}
+
+// Ordered by value: the denominators are positive, so a/b < c/d is a*d < c*b. Products out
+// of range are compared as floats, equal floats in the order of the terms.
+impl Ord for Rational {
+    fn cmp(&self, other: &Rational) -> Ordering {
+        match (
+            self.numerator.checked_mul(other.denominator),
+            other.numerator.checked_mul(self.denominator),
+        ) {
+            (Some(left), Some(right)) => left.cmp(&right),
+            _ => self
+                .to_f64()
+                .partial_cmp(&other.to_f64())
+                .unwrap_or(Ordering::Equal)
+                .then_with(|| {
+                    (self.numerator, self.denominator).cmp(&(other.numerator, other.denominator))
+                }),
+        }
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // padded, so the number lines up in the tables
+        match self.denominator {
+            1 => f.pad(&self.numerator.to_string()),
+            denominator => f.pad(&format!("{}/{}", self.numerator, denominator)),
+        }
+    }
+}
+
+// Parses "-7", "22/7", "2^50" and decimals as "0.25" or ".(3)" with a repeating part
+// in parentheses, every term without a sign but the leading one.
+impl FromStr for Rational {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Rational, String> {
+        let invalid = || {
+            format!(
+                "invalid number \"{}\": expected an integer, a decimal, a fraction as 22/7 or a power as 2^50",
+                s
+            )
+        };
+        let trimmed = s.trim();
+        let (negative, unsigned) = match trimmed.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, trimmed),
+        };
+        let term = |x: &str| match !x.is_empty() && x.chars().all(|c| c.is_ascii_digit()) {
+            true => x.parse::<i128>().map_err(|_| invalid()),
+            false => Err(invalid()),
+        };
+
+        let value = if let Some((numerator, denominator)) = unsigned.split_once('/') {
+            Rational::new(term(numerator)?, term(denominator)?).ok_or_else(invalid)?
+        } else if let Some((base, exponent)) = unsigned.split_once('^') {
+            Rational::integer(term(base)?)
+                .checked_pow(&Rational::integer(term(exponent)?))
+                .map_err(|_| invalid())?
+        } else if let Some((whole, fraction)) = unsigned.split_once('.') {
+            let (fixed, repeating) = match fraction.strip_suffix(')').map(|x| x.split_once('(')) {
+                Some(Some((fixed, repeating))) => (fixed, Some(term(repeating)?)),
+                Some(None) => return Err(invalid()),
+                None => (fraction, None),
+            };
+            let scale = 10_i128
+                .checked_pow(fixed.len() as u32)
+                .ok_or_else(invalid)?;
+            let whole = match whole.is_empty() {
+                true => 0,
+                false => term(whole)?,
+            };
+            let fixed_part = match (fixed.is_empty(), repeating) {
+                (true, Some(_)) => 0,
+                _ => term(fixed)?,
+            };
+            let numerator = whole
+                .checked_mul(scale)
+                .and_then(|x| x.checked_add(fixed_part))
+                .ok_or_else(invalid)?;
+            let value = Rational::new(numerator, scale).ok_or_else(invalid)?;
+            match repeating {
+                // the repeating digits r of n digits add r / (10^n - 1) of the last fixed digit
+                Some(repeating) => {
+                    let digits = fraction.len() - fixed.len() - 2;
+                    let period = 10_i128
+                        .checked_pow(digits as u32)
+                        .and_then(|x| scale.checked_mul(x - 1))
+                        .ok_or_else(invalid)?;
+                    Rational::new(repeating, period)
+                        .and_then(|x| value.checked_add(&x))
+                        .ok_or_else(invalid)?
+                }
+                None => value,
+            }
+        } else {
+            Rational::integer(term(unsigned)?)
+        };
+        match negative {
+            true => Rational::ZERO.checked_sub(&value).ok_or_else(invalid),
+            false => Ok(value),
+        }
+    }
+}
+
+// Integers are written as numbers, fractions and integers out of the 64-bit range
+// as strings, which every format reads back.
+impl Serialize for Rational {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self.to_integer().map(i64::try_from) {
+            Some(Ok(integer)) => serializer.serialize_i64(integer),
+            _ => serializer.collect_str(self),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for Rational {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Rational, D::Error> {
+        deserializer.deserialize_any(RationalVisitor)
+    }
+}
+
+struct RationalVisitor;
+
+impl<'de> Visitor<'de> for RationalVisitor {
+    type Value = Rational;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "an integer or a string as \"-7\", \"22/7\" or \"2^50\"")
+    }
+
+    fn visit_i64<E: de::Error>(self, value: i64) -> Result<Rational, E> {
+        Ok(Rational::integer(value as i128))
+    }
+
+    fn visit_u64<E: de::Error>(self, value: u64) -> Result<Rational, E> {
+        Ok(Rational::from(value))
+    }
+
+    fn visit_str<E: de::Error>(self, value: &str) -> Result<Rational, E> {
+        value.parse().map_err(E::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rational(value: &str) -> Rational {
+        value.parse().unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(rational("-7"), Rational::integer(-7));
+        assert_eq!(rational("22/7"), Rational::new(22, 7).unwrap());
+        assert_eq!(rational("-6/4"), Rational::new(-3, 2).unwrap());
+        assert_eq!(rational("2^50"), Rational::integer(1 << 50));
+        assert_eq!(rational(".5"), Rational::new(1, 2).unwrap());
+        assert_eq!(rational("0.25"), Rational::new(1, 4).unwrap());
+        assert_eq!(rational("-.5"), Rational::new(-1, 2).unwrap());
+        assert_eq!(rational(".(3)"), Rational::new(1, 3).unwrap());
+        assert_eq!(rational("1.2(3)"), Rational::new(37, 30).unwrap());
+        assert_eq!(rational("0.(142857)"), Rational::new(1, 7).unwrap());
+        for invalid in [
+            "", "-", "x", "1/0", "1/-2", "2^-1", "1.2(", ".()", "1.(2)3", "--1",
+        ]
+        .iter()
+        {
+            assert!(invalid.parse::<Rational>().is_err(), "{}", invalid);
+        }
+    }
+
+    #[test]
+    fn ordering() {
+        let mut numbers: Vec<Rational> = ["3", "-1/3", "22/7", "-7", "1/2", "2^100"]
+            .iter()
+            .map(|x| rational(x))
+            .collect();
+        numbers.sort();
+        let sorted: Vec<String> = numbers.iter().map(|x| x.to_string()).collect();
+        assert_eq!(
+            sorted,
+            [
+                "-7",
+                "-1/3",
+                "1/2",
+                "3",
+                "22/7",
+                "1267650600228229401496703205376"
+            ]
+        );
+    }
+
+    #[test]
+    fn powers() {
+        let power = |base: &str, exponent: &str| rational(base).checked_pow(&rational(exponent));
+        assert_eq!(power("4", "3/2"), Ok(Rational::integer(8)));
+        assert_eq!(power("8/27", "2/3"), Ok(Rational::new(4, 9).unwrap()));
+        assert_eq!(power("2", "-2"), Ok(Rational::new(1, 4).unwrap()));
+        assert_eq!(power("-2", "3"), Ok(Rational::integer(-8)));
+        assert_eq!(power("2", "1/2"), Err(EvalError::Irrational));
+        assert_eq!(power("-8", "1/3"), Err(EvalError::NegativeBase));
+        assert_eq!(power("0", "-1"), Err(EvalError::DivisionByZero));
+        assert_eq!(power("2", "200"), Err(EvalError::Overflow(Operator::POWER)));
+    }
+
+    #[test]
+    fn floats() {
+        let float = |value: f64| Rational::from_f64(value, 999);
+        assert_eq!(float(2.0 / 3.0), Rational::new(2, 3));
+        assert_eq!(float(-1.0 / 3.0), Rational::new(-1, 3));
+        assert_eq!(float(-7.0), Some(Rational::integer(-7)));
+        assert_eq!(float(0.1 + 0.2), Rational::new(3, 10));
+        assert_eq!(float(500.0 / 999.0), Rational::new(500, 999));
+        assert_eq!(float(1.0 / 1001.0), None);
+        assert_eq!(float(2f64.sqrt()), None);
+        assert_eq!(float(f64::NAN), None);
+        assert_eq!(float(1e300), None);
+    }
+
+    #[test]
+    fn roots() {
+        assert_eq!(integer_root(0, 3), Some(0));
+        assert_eq!(integer_root(1, 7), Some(1));
+        assert_eq!(integer_root(81, 4), Some(3));
+        assert_eq!(integer_root(80, 4), None);
+        assert_eq!(integer_root(-8, 3), None);
+        // the float roots of these are off by more than 1
+        let large = 1_000_000_000_000_000_003_i128;
+        assert_eq!(integer_root(large * large, 2), Some(large));
+        assert_eq!(integer_root(large * large - 1, 2), None);
+        let cube = 123_456_789_012_i128;
+        assert_eq!(integer_root(cube.pow(3), 3), Some(cube));
+        assert_eq!(integer_root(1 << 126, 126), Some(2));
+        assert_eq!(integer_root(i128::MAX, 2), None);
+    }
+
+    #[test]
+    fn matches() {
+        let third = rational("1/3");
+        assert!(third.matches(".(3)", 1.0 / 3.0));
+        assert!(third.matches("1 3 /", 1.0 / 3.0));
+        assert!(!third.matches("1 2 /", 0.5));
+        // close enough to be compared exactly, but not equal
+        assert!(!third.matches("3333333333 10000000000 /", 0.3333333333));
+        // irrational values are compared as floats
+        assert!(!rational("2").matches("2 1 2 / ^", 2f64.sqrt()));
+    }
+}
@@ -0,0 +1,294 @@
+use crate::operators::Operator;
+use crate::rational::Rational;
+
+// Moduli checked by the analysis.
+const MODULI: std::ops::RangeInclusive<u64> = 2..=16;
+
+// Residues of every expression over the operands in their order, modulo small numbers.
+// Only integer operators keep residues meaningful, so the analysis is made for operator
+// sets of `_ + - *` alone, and only with concatenation of adjacent operands: a
+// concatenated result would depend on the number of digits of its right operand.
+pub struct ResidueAnalysis {
+    operands: Vec<String>,
+    operations: Vec<String>,
+    reachable: Vec<(u64, Vec<bool>)>, // (modulus, whether the residue is reachable)
+}
+
+impl ResidueAnalysis {
+    // `None` when the analysis cannot be made for the operators or the operands.
+    pub fn new(
+        operands: &[String],
+        operations: &[String],
+        concatenate_results: bool,
+    ) -> Option<ResidueAnalysis> {
+        let operators = operations
+            .iter()
+            .map(|x| Operator::from_symbol(x))
+            .collect::<Option<Vec<Operator>>>()?;
+        if operands.is_empty()
+            || concatenate_results
+            || operators
+                .iter()
+                .any(|x| !matches!(x.symbol(), "_" | "+" | "-" | "*"))
+            || !operands
+                .iter()
+                .all(|x| !x.is_empty() && x.chars().all(|c| c.is_ascii_digit()))
+        {
+            return None;
+        }
+        let reachable = MODULI
+            .map(|modulus| (modulus, reachable_residues(operands, &operators, modulus)))
+            .collect();
+        Some(ResidueAnalysis {
+            operands: operands.to_vec(),
+            operations: operations.to_vec(),
+            reachable,
+        })
+    }
+
+    // Why no expression can be equal to the number, `None` when the residues allow it.
+    pub fn check(&self, number: &Rational) -> Option<String> {
+        let number = match number.to_integer() {
+            Some(integer) => integer,
+            None => {
+                return Some(format!(
+                    "{} is not an integer, but every expression over {} with {} is an integer",
+                    number,
+                    self.operands.join(" "),
+                    self.operations.join(" ")
+                ))
+            }
+        };
+        let residue = |modulus: u64| number.rem_euclid(modulus as i128) as u64;
+        self.reachable
+            .iter()
+            .find(|(modulus, residues)| !residues[residue(*modulus) as usize])
+            .map(|(modulus, residues)| {
+                let allowed: Vec<String> = residues
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, reachable)| **reachable)
+                    .map(|(residue, _)| residue.to_string())
+                    .collect();
+                format!(
+                    "{} is {} mod {}, but every expression over {} with {} is {} mod {}",
+                    number,
+                    residue(*modulus),
+                    modulus,
+                    self.operands.join(" "),
+                    self.operations.join(" "),
+                    allowed.join(" or "),
+                    modulus
+                )
+            })
+    }
+}
+
+// Residue of the number written by the operands one after another.
+fn concatenated_residue(operands: &[String], modulus: u64) -> u64 {
+    digits_residue(&operands.concat(), modulus)
+}
+
+fn digits_residue(digits: &str, modulus: u64) -> u64 {
+    digits.chars().fold(0, |residue, digit| {
+        ((residue as u128 * 10 + digit.to_digit(10).unwrap_or(0) as u128) % modulus as u128) as u64
+    })
+}
+
+// Value of an RPN expression in Z/mZ. Sums, differences and products are taken modulo m,
+// a division multiplies by the inverse of the divisor and fails unless it is coprime
+// with m. A concatenation and the exponent of a power depend on the digits of an integer,
+// not on its residue, so they only take operands or runs of concatenated operands.
+pub fn evaluate_modulo(expr: &str, modulus: u64) -> Result<u64, String> {
+    if modulus == 0 {
+        return Err("The modulus must be positive".to_string());
+    }
+    // (residue, digits of the value when it is written by operands alone)
+    let mut stack: Vec<(u64, Option<String>)> = Vec::new();
+    for token in expr.split_whitespace() {
+        match Operator::from_symbol(token) {
+            Some(operator) => {
+                let right = stack.pop();
+                let left = stack.pop();
+                match (left, right) {
+                    (Some(left), Some(right)) => {
+                        stack.push(apply_modulo(&operator, left, right, modulus)?)
+                    }
+                    _ => return Err("Unsufficient operands before operator".to_string()),
+                }
+            }
+            None if !token.is_empty() && token.chars().all(|c| c.is_ascii_digit()) => {
+                stack.push((digits_residue(token, modulus), Some(token.to_string())))
+            }
+            None => return Err(format!("Cannot parse operand \"{}\"", token)),
+        }
+    }
+    match (stack.pop(), stack.is_empty()) {
+        (Some((residue, _)), true) => Ok(residue),
+        _ => Err("Remaining untreated operands. Probably missing operator.".to_string()),
+    }
+}
+
+fn apply_modulo(
+    operator: &Operator,
+    left: (u64, Option<String>),
+    right: (u64, Option<String>),
+    modulus: u64,
+) -> Result<(u64, Option<String>), String> {
+    let m = modulus as u128;
+    let (a, b) = (left.0 as u128, right.0 as u128);
+    let residue = match operator.symbol() {
+        "+" => (a + b) % m,
+        "-" => (a + m - b) % m,
+        "*" => a * b % m,
+        "/" => match inverse(b, m) {
+            Some(inverse) => a * inverse % m,
+            None => return Err(format!("{} is not invertible modulo {}", b, m)),
+        },
+        "_" => match (left.1, right.1) {
+            (Some(x), Some(y)) => {
+                let digits = x + &y;
+                return Ok((digits_residue(&digits, modulus), Some(digits)));
+            }
+            _ => return Err("Only operands can be concatenated modulo a number".to_string()),
+        },
+        "^" => match right.1 {
+            Some(exponent) => power_modulo(a, &exponent, m),
+            None => return Err("Only operands can be exponents modulo a number".to_string()),
+        },
+        symbol => return Err(format!("{} has no value modulo a number", symbol)),
+    };
+    Ok((residue as u64, None))
+}
+
+// base^exponent mod m for the exponent written in decimal digits, one digit at a time.
+fn power_modulo(base: u128, exponent: &str, m: u128) -> u128 {
+    let small_power = |x: u128, n: u32| (0..n).fold(1 % m, |power, _| power * x % m);
+    exponent.chars().fold(1 % m, |power, digit| {
+        small_power(power, 10) * small_power(base, digit.to_digit(10).unwrap_or(0)) % m
+    })
+}
+
+// Inverse of the value modulo m by the extended Euclidean algorithm, `None` unless
+// the value and m are coprime.
+fn inverse(value: u128, m: u128) -> Option<u128> {
+    let (mut old_r, mut r) = (value as i128, m as i128);
+    let (mut old_s, mut s) = (1_i128, 0_i128);
+    while r != 0 {
+        let quotient = old_r / r;
+        (old_r, r) = (r, old_r - quotient * r);
+        (old_s, s) = (s, old_s - quotient * s);
+    }
+    match old_r == 1 {
+        true => Some(old_s.rem_euclid(m as i128) as u128),
+        false => None,
+    }
+}
+
+// `residues[i][j]` are the residues of the expressions over operands i..=j: a run of
+// concatenated operands, or an operator applied to expressions over i..=k and k+1..=j.
+fn reachable_residues(operands: &[String], operators: &[Operator], modulus: u64) -> Vec<bool> {
+    let count = operands.len();
+    let size = modulus as usize;
+    let concatenation = operators.contains(&Operator::CONCATENATION);
+    let mut residues = vec![vec![Vec::<bool>::new(); count]; count];
+    for length in 1..=count {
+        for first in 0..=(count - length) {
+            let last = first + length - 1;
+            let mut reachable = vec![false; size];
+            if length == 1 || concatenation {
+                reachable[concatenated_residue(&operands[first..=last], modulus) as usize] = true;
+            }
+            for split in first..last {
+                let left = &residues[first][split];
+                let right = &residues[split + 1][last];
+                for (a, _) in left.iter().enumerate().filter(|(_, x)| **x) {
+                    for (b, _) in right.iter().enumerate().filter(|(_, x)| **x) {
+                        for operator in operators {
+                            let result = match operator.symbol() {
+                                "+" => (a + b) % size,
+                                "-" => (a + size - b) % size,
+                                "*" => (a * b) % size,
+                                _ => continue,
+                            };
+                            reachable[result] = true;
+                        }
+                    }
+                }
+            }
+            residues[first][last] = reachable;
+        }
+    }
+    residues[0][count - 1].clone()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn check() {
+        let analysis = ResidueAnalysis::new(
+            &strings(&["2", "4", "6"]),
+            &strings(&["+", "-", "*"]),
+            false,
+        )
+        .unwrap();
+        // every expression over even operands is even
+        let odd = analysis.check(&Rational::integer(7)).unwrap();
+        assert!(odd.contains("7 is 1 mod 2"), "{}", odd);
+        assert!(analysis.check(&"1/2".parse().unwrap()).is_some());
+        // 2 + 4 + 6, 2 * 4 * 6, 2 - 4 - 6
+        for number in [12, 48, -8].iter() {
+            assert_eq!(analysis.check(&Rational::integer(*number)), None);
+        }
+    }
+
+    #[test]
+    fn analysis_not_made() {
+        let operands = strings(&["1", "2", "3"]);
+        assert!(ResidueAnalysis::new(&operands, &strings(&["+", "/"]), false).is_none());
+        assert!(ResidueAnalysis::new(&operands, &strings(&["_", "+"]), true).is_none());
+        assert!(ResidueAnalysis::new(&strings(&[".5", "2"]), &strings(&["+"]), false).is_none());
+        assert!(ResidueAnalysis::new(&operands, &strings(&["_", "+"]), false).is_some());
+    }
+
+    #[test]
+    fn modulo() {
+        assert_eq!(evaluate_modulo("7 5 + 3 *", 5), Ok(1));
+        assert_eq!(evaluate_modulo("2 5 -", 7), Ok(4));
+        assert_eq!(evaluate_modulo("1 2 _ 3 _", 10), Ok(3));
+        // 3 / 2 is 3 * 3 modulo 5, as 2 * 3 is 1
+        assert_eq!(evaluate_modulo("3 2 /", 5), Ok(4));
+        assert_eq!(
+            evaluate_modulo("3 2 /", 4),
+            Err("2 is not invertible modulo 4".to_string())
+        );
+        assert!(evaluate_modulo("2 3 + 4 _", 10).is_err());
+        assert!(evaluate_modulo("1 2", 5).is_err());
+        assert!(evaluate_modulo("1", 0).is_err());
+    }
+
+    #[test]
+    fn inverses() {
+        assert_eq!(inverse(3, 7), Some(5));
+        assert_eq!(inverse(1, 2), Some(1));
+        assert_eq!(inverse(6, 9), None);
+        assert_eq!(inverse(0, 5), None);
+    }
+
+    #[test]
+    fn powers() {
+        // the exponent is reduced one digit at a time: 3^123 mod 7 is 3^3 mod 7
+        assert_eq!(power_modulo(3, "123", 7), 6);
+        assert_eq!(power_modulo(2, "10", 1000), 24);
+        assert_eq!(power_modulo(5, "0", 7), 1);
+        assert_eq!(power_modulo(5, "0", 1), 0);
+        assert_eq!(evaluate_modulo("2 1 0 _ ^", 1000), Ok(24));
+        assert!(evaluate_modulo("2 1 2 + ^", 1000).is_err());
+    }
+}
@@ -0,0 +1,454 @@
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+use crate::operators::{Associativity, Operator};
+use crate::rational::Rational;
+
+// bounds for division and power results, stored as f64 bits
+static LOWER_BOUND: AtomicU64 = AtomicU64::new(0.001_f64.to_bits());
+static UPPER_BOUND: AtomicU64 = AtomicU64::new(1073741824.0_f64.to_bits());
+
+pub fn set_bounds(lower_bound: f64, upper_bound: f64) {
+    LOWER_BOUND.store(lower_bound.to_bits(), Ordering::Relaxed);
+    UPPER_BOUND.store(upper_bound.to_bits(), Ordering::Relaxed);
+}
+
+pub(crate) fn get_bounds() -> (f64, f64) {
+    (
+        f64::from_bits(LOWER_BOUND.load(Ordering::Relaxed)),
+        f64::from_bits(UPPER_BOUND.load(Ordering::Relaxed)),
+    )
+}
+
+// every value is an i128, see `evaluate_integer`
+static INTEGER_ONLY: AtomicBool = AtomicBool::new(false);
+
+pub fn set_integer_only(integer_only: bool) {
+    INTEGER_ONLY.store(integer_only, Ordering::Relaxed);
+}
+
+pub fn is_integer_only() -> bool {
+    INTEGER_ONLY.load(Ordering::Relaxed)
+}
+
+// a negative base takes real roots of odd degree, see `Operator::POWER`
+static REAL_ROOTS: AtomicBool = AtomicBool::new(false);
+
+pub fn set_real_roots(real_roots: bool) {
+    REAL_ROOTS.store(real_roots, Ordering::Relaxed);
+}
+
+pub fn is_real_roots() -> bool {
+    REAL_ROOTS.load(Ordering::Relaxed)
+}
+
+// Why an expression has no value: a malformed expression, or an operator applied out of
+// its domain or giving a value the rules do not allow.
+#[derive(Debug, Clone, PartialEq)]
+pub enum EvalError {
+    Parse(String),
+    MissingOperands,
+    RemainingOperands,
+    DivisionByZero,
+    OutOfBounds(Operator),
+    Overflow(Operator),
+    NegativeBase,
+    UnrealConcatenation,
+    InexactDivision,
+    NegativeExponent,
+    Irrational,
+    NotInteger,
+}
+
+impl EvalError {
+    // Whether the expression itself is wrong, not one of its values.
+    pub fn is_malformed(&self) -> bool {
+        matches!(
+            self,
+            EvalError::Parse(_) | EvalError::MissingOperands | EvalError::RemainingOperands
+        )
+    }
+}
+
+impl fmt::Display for EvalError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EvalError::Parse(token) => write!(f, "Cannot parse operand \"{}\"", token),
+            EvalError::MissingOperands => write!(f, "Unsufficient operands before operator"),
+            EvalError::RemainingOperands => write!(
+                f,
+                "Remaining untreated operands. Probably missing operator."
+            ),
+            EvalError::DivisionByZero => write!(f, "Division by zero"),
+            EvalError::OutOfBounds(operator) => {
+                write!(f, "Result of {} is too big or too small", operator.symbol())
+            }
+            EvalError::Overflow(operator) => {
+                write!(f, "Result of {} overflows", operator.symbol())
+            }
+            EvalError::NegativeBase => {
+                write!(f, "Negative base of a power with a fractional exponent")
+            }
+            EvalError::UnrealConcatenation => write!(f, "Concatenation is unreal"),
+            EvalError::InexactDivision => write!(f, "Division is not exact"),
+            EvalError::NegativeExponent => write!(f, "Exponent is not a non-negative integer"),
+            EvalError::Irrational => write!(f, "Power is irrational"),
+            EvalError::NotInteger => write!(f, "Value is not an integer"),
+        }
+    }
+}
+
+impl From<EvalError> for String {
+    fn from(error: EvalError) -> String {
+        error.to_string()
+    }
+}
+
+enum OperationElt {
+    Operator(Operator),
+    Operand(f64),
+}
+
+// Value of an operand: a number as 12 or .5, or a decimal with a repeating part as .(3).
+pub fn parse_operand(token: &str) -> Option<f64> {
+    match token.parse::<f64>() {
+        // "inf" and "NaN" parse as floats, but are no operands
+        Ok(val) => Some(val).filter(|x| x.is_finite()),
+        Err(_) if token.ends_with(')') => token.parse::<Rational>().ok().map(|x| x.to_f64()),
+        Err(_) => None,
+    }
+}
+
+fn tokenizer(expr: &str) -> Result<Vec<OperationElt>, EvalError> {
+    expr.split_whitespace()
+        .map(|el| match Operator::from_symbol(el) {
+            Some(operator) => Ok(OperationElt::Operator(operator)),
+            None => match parse_operand(el) {
+                Some(val) => Ok(OperationElt::Operand(val)),
+                None => Err(EvalError::Parse(el.to_string())),
+            },
+        })
+        .collect()
+}
+
+// The operands of an operator of the given arity, taken from the top of the stack.
+fn pop_operands<T>(stack: &mut Vec<T>, arity: usize) -> Result<Vec<T>, EvalError> {
+    if stack.len() < arity {
+        return Err(EvalError::MissingOperands);
+    }
+    Ok(stack.split_off(stack.len() - arity))
+}
+
+pub fn evaluate(expr: &str) -> Result<f64, EvalError> {
+    if is_integer_only() {
+        return evaluate_integer(expr).map(|x| x as f64);
+    }
+    match tokenizer(expr) {
+        Ok(tokens) => {
+            let bounds = get_bounds();
+            let mut stack: Vec<f64> = Vec::new();
+            for token in tokens {
+                match token {
+                    OperationElt::Operator(operator) => {
+                        let operands = pop_operands(&mut stack, operator.arity())?;
+                        stack.push(apply(&operator, &operands, bounds)?);
+                    }
+                    OperationElt::Operand(val) => stack.push(val),
+                }
+            }
+            if stack.len() != 1 {
+                return Err(EvalError::RemainingOperands);
+            }
+
+            match stack.pop() {
+                None => Err(EvalError::RemainingOperands),
+                Some(x) => Ok(x),
+            }
+        }
+        Err(err) => Err(err),
+    }
+}
+
+// Every operator checks its operands against its domain, the result is checked here:
+// a value is never NaN or infinite, so none reaches a comparison or a uniqueness key.
+pub(crate) fn apply(
+    operator: &Operator,
+    operands: &[f64],
+    bounds: (f64, f64),
+) -> Result<f64, EvalError> {
+    let op_result = operator.evaluate(operands, bounds)?;
+    match op_result.is_finite() {
+        true => Ok(op_result),
+        false => Err(EvalError::Overflow(*operator)),
+    }
+}
+
+// Exact value of the expression under the rules of `evaluate`. Fails where `evaluate` does,
+// and also when a power is irrational or a value overflows 128-bit fractions.
+pub fn evaluate_exact(expr: &str) -> Result<Rational, EvalError> {
+    if is_integer_only() {
+        return evaluate_integer(expr).map(Rational::integer);
+    }
+    let bounds = get_bounds();
+    let mut stack: Vec<Rational> = Vec::new();
+    for token in expr.split_whitespace() {
+        match Operator::from_symbol(token) {
+            Some(operator) => {
+                let operands = pop_operands(&mut stack, operator.arity())?;
+                stack.push(operator.evaluate_exact(&operands, bounds)?);
+            }
+            None => stack.push(
+                token
+                    .parse::<Rational>()
+                    .map_err(|_| EvalError::Parse(token.to_string()))?,
+            ),
+        }
+    }
+    match (stack.pop(), stack.is_empty()) {
+        (Some(value), true) => Ok(value),
+        _ => Err(EvalError::RemainingOperands),
+    }
+}
+
+// Value of the expression when every value is an integer: a division must be exact and
+// a power must have a non-negative exponent, and no value may overflow an i128. The bounds
+// of division and power results are the same as for `evaluate`.
+pub fn evaluate_integer(expr: &str) -> Result<i128, EvalError> {
+    let bounds = get_bounds();
+    let mut stack: Vec<i128> = Vec::new();
+    for token in expr.split_whitespace() {
+        match Operator::from_symbol(token) {
+            Some(operator) => {
+                let operands = pop_operands(&mut stack, operator.arity())?;
+                stack.push(operator.evaluate_integer(&operands, bounds)?);
+            }
+            None => stack.push(token.parse::<i128>().map_err(
+                |_| match token.parse::<Rational>() {
+                    Ok(_) => EvalError::NotInteger,
+                    Err(_) => EvalError::Parse(token.to_string()),
+                },
+            )?),
+        }
+    }
+    match (stack.pop(), stack.is_empty()) {
+        (Some(value), true) => Ok(value),
+        _ => Err(EvalError::RemainingOperands),
+    }
+}
+
+// `apply` under the arithmetic chosen by `set_integer_only`.
+pub(crate) fn apply_value(
+    operator: &Operator,
+    operands: &[f64],
+    bounds: (f64, f64),
+) -> Result<f64, EvalError> {
+    if !is_integer_only() {
+        return apply(operator, operands, bounds);
+    }
+    if operands.iter().any(|x| x.fract() != 0.0) {
+        return Err(EvalError::NotInteger);
+    }
+    let operands: Vec<i128> = operands.iter().map(|x| *x as i128).collect();
+    operator
+        .evaluate_integer(&operands, bounds)
+        .map(|x| x as f64)
+}
+
+// One stack operation of an explained expression: what was done, the stack after it
+// and the infix form of the sub-expression now on top of the stack.
+pub struct Step {
+    pub operation: String,
+    pub stack: Vec<f64>,
+    pub infix: String,
+}
+
+// The operation as it is explained, "1.5 ^ 2" for a binary operator.
+fn describe(operator: &Operator, operands: &[f64]) -> String {
+    match operands {
+        [operand1, operand2] => format!("{} {} {}", operand1, operator.symbol(), operand2),
+        _ => format!(
+            "{}({})",
+            operator.symbol(),
+            operands
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        ),
+    }
+}
+
+// Evaluates the expression step by step. Every entry of the stack keeps the RPN of its
+// sub-expression so that `to_infix` gives the same parentheses as for the whole expression.
+pub fn explain(expr: &str) -> Result<Vec<Step>, String> {
+    let tokens = tokenizer(expr)?;
+    let bounds = get_bounds();
+    let mut stack: Vec<(f64, String)> = Vec::new();
+    let mut steps: Vec<Step> = Vec::new();
+    for (symbol, token) in expr.split_whitespace().zip(tokens) {
+        let operation = match token {
+            OperationElt::Operator(operator) => {
+                let (operands, rpns): (Vec<f64>, Vec<String>) =
+                    pop_operands(&mut stack, operator.arity())
+                        .map_err(|_| {
+                            format!(
+                                "step {}: unsufficient operands before operator \"{}\"",
+                                steps.len() + 1,
+                                symbol
+                            )
+                        })?
+                        .into_iter()
+                        .unzip();
+                let result = apply_value(&operator, &operands, bounds).map_err(|err| {
+                    format!(
+                        "step {}: {}: {}",
+                        steps.len() + 1,
+                        describe(&operator, &operands),
+                        err
+                    )
+                })?;
+                stack.push((result, format!("{} {}", rpns.join(" "), symbol)));
+                format!("{} = {}", describe(&operator, &operands), result)
+            }
+            OperationElt::Operand(val) => {
+                stack.push((val, symbol.to_string()));
+                format!("push {}", symbol)
+            }
+        };
+        let infix = match stack.last() {
+            Some((_, rpn)) => to_infix(rpn)?,
+            None => String::new(),
+        };
+        steps.push(Step {
+            operation,
+            stack: stack.iter().map(|(value, _)| *value).collect(),
+            infix,
+        });
+    }
+    if stack.len() != 1 {
+        return Err("Remaining untreated operands. Probably missing operator.".to_string());
+    }
+    Ok(steps)
+}
+
+fn _calculate(result: &str) -> f64 {
+    evaluate(result).unwrap()
+}
+
+struct _Entity {
+    value: String,
+    last_op_priority: u8,
+    last_operator: Option<Operator>,
+}
+
+impl _Entity {
+    // operands are written as they are given, .5 or .(3) as well as 12
+    fn _new(value: &str) -> _Entity {
+        _Entity {
+            value: value.to_string(),
+            last_op_priority: 127,
+            last_operator: None,
+        }
+    }
+
+    fn _from(value: String, last_operator: Operator) -> _Entity {
+        _Entity {
+            value,
+            last_op_priority: last_operator.precedence(),
+            last_operator: Some(last_operator),
+        }
+    }
+}
+
+// Whether the operand is parenthesized as the first or the last operand of the operator.
+fn needs_parentheses(operator: &Operator, operand: &_Entity, first: bool, last: bool) -> bool {
+    let priority = operand.last_op_priority;
+    priority < operator.precedence()
+        || priority == operator.precedence()
+            && match operator.associativity() {
+                Associativity::Full => {
+                    !first
+                        && !operand
+                            .last_operator
+                            .is_some_and(|x| operator.regroups_with(&x))
+                }
+                Associativity::Left => !first,
+                Associativity::Right => !last,
+                Associativity::None => true,
+            }
+}
+
+pub fn to_infix(expr: &str) -> Result<String, String> {
+    match evaluate(expr) {
+        Ok(_) => {
+            let tokens = tokenizer(expr)?;
+            let mut stack: Vec<_Entity> = Vec::new();
+            for (symbol, token) in expr.split_whitespace().zip(tokens) {
+                match token {
+                    OperationElt::Operator(operator) => {
+                        let operands = pop_operands(&mut stack, operator.arity())?;
+                        let last = operands.len() - 1;
+                        let written: Vec<String> = operands
+                            .into_iter()
+                            .enumerate()
+                            .map(|(idx, operand)| {
+                                match needs_parentheses(&operator, &operand, idx == 0, idx == last)
+                                {
+                                    true => format!("({})", operand.value),
+                                    false => operand.value,
+                                }
+                            })
+                            .collect();
+                        stack.push(_Entity::_from(operator.infix(&written), operator));
+                    }
+                    OperationElt::Operand(_) => stack.push(_Entity::_new(symbol)),
+                }
+            }
+
+            if stack.len() != 1 {
+                return Err("Remaining untreated operands. Probably missing operator.".to_string());
+            }
+
+            match stack.pop() {
+                None => Err("Remaining untreated operands. Probably missing operator.".to_string()),
+                Some(x) => Ok(x.value),
+            }
+        }
+        Err(_) => Err(String::from("Could not evaluate the expression")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn malformed() {
+        assert_eq!(
+            evaluate("1 inf +"),
+            Err(EvalError::Parse("inf".to_string()))
+        );
+        assert_eq!(evaluate("NaN"), Err(EvalError::Parse("NaN".to_string())));
+        assert_eq!(evaluate("1 +"), Err(EvalError::MissingOperands));
+        assert_eq!(evaluate("1 2"), Err(EvalError::RemainingOperands));
+        assert_eq!(
+            evaluate_exact("1 x +"),
+            Err(EvalError::Parse("x".to_string()))
+        );
+        assert_eq!(
+            evaluate_integer("1 2 3 +"),
+            Err(EvalError::RemainingOperands)
+        );
+        assert!(EvalError::MissingOperands.is_malformed());
+        assert!(!EvalError::DivisionByZero.is_malformed());
+    }
+
+    #[test]
+    fn decimals() {
+        assert_eq!(evaluate_exact(".(3) 3 *"), Ok(Rational::integer(1)));
+        assert_eq!(evaluate_exact("1.2(3)"), Ok(Rational::new(37, 30).unwrap()));
+        assert_eq!(evaluate_exact("2 .5 /"), Ok(Rational::integer(4)));
+        assert_eq!(evaluate_integer(".(3)"), Err(EvalError::NotInteger));
+        assert_eq!(to_infix(".5 .(3) +"), Ok(".5 + .(3)".to_string()));
+    }
+}
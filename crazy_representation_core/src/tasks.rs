@@ -0,0 +1,87 @@
+use std::sync::{Arc, Condvar, Mutex};
+use threadpool::ThreadPool;
+
+// Thread pool with a bounded queue: `execute` waits while `queue_size` tasks are already
+// waiting, so the thread producing the tasks does not run far ahead of the workers.
+pub struct TaskQueue {
+    pool: ThreadPool,
+    limit: usize,
+    pending: Arc<(Mutex<usize>, Condvar)>, // tasks queued or running
+}
+
+impl TaskQueue {
+    pub fn new(threads: usize, queue_size: usize) -> TaskQueue {
+        let threads = threads.max(1);
+        TaskQueue {
+            pool: ThreadPool::new(threads),
+            limit: threads + queue_size.max(1),
+            pending: Arc::new((Mutex::new(0), Condvar::new())),
+        }
+    }
+
+    pub fn execute<F>(&self, job: F)
+    where
+        F: FnOnce() + Send + 'static,
+    {
+        let (count, finished) = &*self.pending;
+        let mut pending = count.lock().unwrap();
+        while *pending >= self.limit {
+            pending = finished.wait(pending).unwrap();
+        }
+        *pending += 1;
+        drop(pending);
+
+        let task_pending = TaskPending(Arc::clone(&self.pending));
+        self.pool.execute(move || {
+            let _task_pending = task_pending;
+            job();
+        });
+    }
+
+    pub fn join(&self) {
+        self.pool.join();
+    }
+}
+
+// Counts a task out when it is dropped at its end, also when the task panics.
+struct TaskPending(Arc<(Mutex<usize>, Condvar)>);
+
+impl Drop for TaskPending {
+    fn drop(&mut self) {
+        let (count, finished) = &*self.0;
+        *count.lock().unwrap() -= 1;
+        finished.notify_one();
+    }
+}
+
+// Default number of queued tasks per worker.
+pub const QUEUE_SIZE_PER_THREAD: usize = 64;
+
+pub fn default_queue_size(threads: usize) -> usize {
+    threads * QUEUE_SIZE_PER_THREAD
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn panicking_tasks() {
+        let queue = TaskQueue::new(1, 1);
+        let done = Arc::new(AtomicUsize::new(0));
+        // more panics than the queue holds, a task left counted would block `execute`
+        for _ in 0..4 {
+            queue.execute(|| panic!("task failed"));
+        }
+        for _ in 0..4 {
+            let done = Arc::clone(&done);
+            queue.execute(move || {
+                done.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+        queue.join();
+        assert_eq!(done.load(Ordering::SeqCst), 4);
+        assert_eq!(*queue.pending.0.lock().unwrap(), 0);
+    }
+}
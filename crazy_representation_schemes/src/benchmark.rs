@@ -0,0 +1,106 @@
+use std::time::Instant;
+
+use crazy_representation_core::configs_lib::{Config, Format, Strategy};
+use crazy_representation_core::rational::Rational;
+
+use crate::report::{self, Record};
+use crate::strategies;
+
+// Runs every strategy `repeats` times for each operands set and target. The rows of one
+// operands set and target follow each other, so the strategies are compared line by line.
+pub fn run(
+    configs: &Config,
+    targets: &[Rational],
+    operand_sets: &[Vec<String>],
+    strategy_kinds: &[Strategy],
+    repeats: usize,
+) {
+    let format = configs.get_format();
+    let bin_operations = configs.get_operations();
+    let search_strategies: Vec<_> = strategy_kinds
+        .iter()
+        .map(|kind| strategies::create(*kind, configs))
+        .collect();
+    let operands_width = operand_sets
+        .iter()
+        .map(|operands| operands.join(" ").len())
+        .max()
+        .unwrap_or(0)
+        .max(8);
+
+    if format == Format::Text {
+        println!(
+            "{:<18}  {:<ow$}  {:>8}  {:>5}  {:>10}  {:>10}  {:>12}  {:>10}  {:>10}  {:>10}",
+            "strategy",
+            "operands",
+            "target",
+            "found",
+            "mean, s",
+            "best, s",
+            "expr/s",
+            "peak dedup",
+            "skipped",
+            "cut",
+            ow = operands_width
+        );
+    }
+    for operands in operand_sets {
+        for number in targets {
+            for search_strategy in &search_strategies {
+                let mut total_time = 0.0;
+                let mut best_time = f64::INFINITY;
+                let mut total_evaluated = 0;
+                let mut total_pruned = 0;
+                let mut dedup_peak = 0;
+                let mut total_skipped = 0;
+                let mut total_cut = 0;
+                let mut result = None;
+                for _ in 0..repeats {
+                    let start = Instant::now();
+                    let outcome = search_strategy.search(operands, &bin_operations, *number);
+                    let elapsed = start.elapsed().as_secs_f64();
+                    total_time += elapsed;
+                    best_time = best_time.min(elapsed);
+                    total_evaluated += outcome.evaluated;
+                    total_pruned += outcome.pruned;
+                    dedup_peak = dedup_peak.max(outcome.dedup_peak);
+                    total_skipped += outcome.skipped;
+                    total_cut += outcome.cut;
+                    result = outcome.result;
+                }
+                let expressions_per_sec = if total_time > 0.0 {
+                    total_evaluated as f64 / total_time
+                } else {
+                    0.0
+                };
+
+                if format == Format::Text {
+                    println!(
+                        "{:<18}  {:<ow$}  {:>8}  {:>5}  {:>10.4}  {:>10.4}  {:>12.0}  {:>10}  {:>10}  {:>10}",
+                        search_strategy.name(),
+                        operands.join(" "),
+                        number,
+                        if result.is_some() { "yes" } else { "no" },
+                        total_time / repeats as f64,
+                        best_time,
+                        expressions_per_sec,
+                        dedup_peak,
+                        total_skipped / repeats as u64,
+                        total_cut / repeats as u64,
+                        ow = operands_width
+                    );
+                }
+                let mut record = Record::benchmark(*number, operands, result.as_deref())
+                    .with_stats(
+                        total_time / repeats as f64,
+                        total_evaluated / repeats as u64,
+                        total_pruned / repeats as u64,
+                        search_strategy.name(),
+                    )
+                    .with_cuts(total_skipped / repeats as u64, total_cut / repeats as u64);
+                record.dedup_peak = Some(dedup_peak);
+                report::print_record(format, &record);
+            }
+        }
+    }
+}
@@ -1,23 +1,162 @@
-use std::fs::{File, OpenOptions};
+use dashmap::DashMap;
+use std::collections::BTreeMap;
+use std::fs::File;
 use std::io;
-use std::io::Write;
-use std::sync::RwLock;
+use std::io::{BufWriter, Write};
+use std::sync::{Mutex, RwLock};
 
-lazy_static! {
-    pub static ref RWLOCK_PATH: RwLock<&'static str> = RwLock::new("");
+use crazy_representation_core::rational::Rational;
+
+// Results file shared by all worker threads. Only the first expression found for every
+// number is kept. In sorted mode lines are collected and written ordered by number
+// in `finish_writing`, otherwise they are written as they come through a buffer.
+struct ResultSink {
+    output: Mutex<BufWriter<File>>,
+    written: DashMap<Rational, bool>,
+    sorted: Option<Mutex<BTreeMap<Rational, String>>>,
 }
 
-pub fn _create_file_to_write(path: &'static str) {
-    let _ = File::create(path);
-    let mut rw = RWLOCK_PATH.write().unwrap();
-    *rw = path;
+lazy_static! {
+    static ref SINK: RwLock<Option<ResultSink>> = RwLock::new(None);
 }
 
-pub fn _write_to_file(calculated: &f64, result: &str) -> Result<(), io::Error> {
-    let rw: &str = &RWLOCK_PATH.read().unwrap();
-    if *calculated > 0.0 && *calculated < 9223372036854775807.0 && calculated.fract() == 0.0 {
-        let mut output = OpenOptions::new().write(true).append(true).open(rw)?;
-        write!(output, "{} = {}\n", *calculated as i64, result)?;
+impl ResultSink {
+    fn create(path: &str, sorted: bool) -> Result<ResultSink, io::Error> {
+        let file = File::create(path)?;
+        Ok(ResultSink {
+            output: Mutex::new(BufWriter::new(file)),
+            written: DashMap::new(),
+            sorted: if sorted {
+                Some(Mutex::new(BTreeMap::new()))
+            } else {
+                None
+            },
+        })
+    }
+
+    fn write(&self, number: &Rational, result: &str) -> Result<(), io::Error> {
+        match &self.sorted {
+            Some(lines) => {
+                lines
+                    .lock()
+                    .unwrap()
+                    .entry(*number)
+                    .or_insert_with(|| result.trim().to_string());
+            }
+            None => {
+                if self.written.insert(*number, true).is_none() {
+                    let mut output = self.output.lock().unwrap();
+                    writeln!(output, "{} = {}", number, result.trim())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(&self) -> Result<(), io::Error> {
+        let mut output = self.output.lock().unwrap();
+        if let Some(lines) = &self.sorted {
+            for (number, result) in lines.lock().unwrap().iter() {
+                writeln!(output, "{} = {}", number, result)?;
+            }
+        }
+        output.flush()
     }
+}
+
+pub fn create_file_to_write(path: &str, sorted: bool) -> Result<(), io::Error> {
+    let sink = ResultSink::create(path, sorted)?;
+    *SINK.write().unwrap() = Some(sink);
     Ok(())
 }
+
+// Does nothing until a file is chosen with `create_file_to_write`.
+pub fn write_to_file(number: &Rational, result: &str) -> Result<(), io::Error> {
+    match SINK.read().unwrap().as_ref() {
+        None => Ok(()),
+        Some(sink) => sink.write(number, result),
+    }
+}
+
+// Writes the collected lines of the sorted mode and flushes the buffer.
+pub fn finish_writing() -> Result<(), io::Error> {
+    match SINK.read().unwrap().as_ref() {
+        None => Ok(()),
+        Some(sink) => sink.finish(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Lines of the file written by the sink after the results are given to it.
+    fn written_lines(name: &str, sorted: bool, results: &[(&str, &str)]) -> Vec<String> {
+        let path = std::env::temp_dir().join(format!("crazy_representation_{}.txt", name));
+        let path = path.to_str().unwrap();
+        let sink = ResultSink::create(path, sorted).unwrap();
+        for (number, result) in results {
+            sink.write(&number.parse().unwrap(), result).unwrap();
+        }
+        sink.finish().unwrap();
+        let lines = std::fs::read_to_string(path)
+            .unwrap()
+            .lines()
+            .map(|x| x.to_string())
+            .collect();
+        std::fs::remove_file(path).unwrap();
+        lines
+    }
+
+    #[test]
+    fn first_result_only() {
+        let results = [("3", "1 2 + "), ("1", "1 2 / "), ("3", "1 2 * 1 + ")];
+        assert_eq!(
+            written_lines("sink_unsorted", false, &results),
+            vec!["3 = 1 2 +", "1 = 1 2 /"]
+        );
+    }
+
+    #[test]
+    fn sorted_by_number() {
+        let results = [
+            ("12", "1 2 _ "),
+            ("3", "1 2 + "),
+            ("3", "1 2 * 1 + "),
+            ("1/2", "1 2 / "),
+        ];
+        assert_eq!(
+            written_lines("sink_sorted", true, &results),
+            vec!["1/2 = 1 2 /", "3 = 1 2 +", "12 = 1 2 _"]
+        );
+    }
+
+    #[test]
+    fn negative_and_fractional_numbers() {
+        let results = [
+            ("-7", "1 8 - "),
+            ("22/7", "2 2 _ 7 / "),
+            ("-1/3", "1 2 - 3 / "),
+            ("2^70", "2 7 0 _ ^ "),
+            ("-7", "1 2 3 _ - "),
+        ];
+        assert_eq!(
+            written_lines("sink_rational", false, &results),
+            vec![
+                "-7 = 1 8 -",
+                "22/7 = 2 2 _ 7 /",
+                "-1/3 = 1 2 - 3 /",
+                "1180591620717411303424 = 2 7 0 _ ^"
+            ]
+        );
+        assert_eq!(
+            written_lines("sink_rational_sorted", true, &results),
+            vec![
+                "-7 = 1 8 -",
+                "-1/3 = 1 2 - 3 /",
+                "22/7 = 2 2 _ 7 /",
+                "1180591620717411303424 = 2 7 0 _ ^"
+            ]
+        );
+    }
+}
@@ -0,0 +1,570 @@
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crazy_representation_core::configs_lib::{
+    Arithmetic, Concatenation, Config, Decimals, Powers, Pruning, Trees,
+};
+use crazy_representation_core::rational::Rational;
+use crazy_representation_core::rpn_evaluator;
+
+use crate::generator;
+use crate::schemes_lib;
+use crate::strategies::SearchOutcome;
+
+// Shards per run when the size of a shard is not given.
+const DEFAULT_SHARDS: u128 = 64;
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+const CONNECT_ATTEMPTS: u32 = 50;
+const CONNECT_INTERVAL: Duration = Duration::from_millis(200);
+
+// Messages between the coordinator and a worker, a JSON value per line. They are tagged
+// externally: serde cannot buffer the u128 scheme indices of an internally tagged enum.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum Message {
+    // coordinator to worker: the search, sent once after connecting
+    Setup {
+        number: Rational,
+        operands: Vec<String>,
+        operations: Vec<String>,
+        lower_bound: f64,
+        upper_bound: f64,
+        integer_only: bool,
+        real_roots: bool,
+        concatenate_results: bool,
+        decimal_point: bool,
+        repeating_decimals: bool,
+        canonical_trees: bool,
+        interval_pruning: bool,
+        split_depth: Option<usize>,
+    },
+    // coordinator to worker: schemes with indices in start..end
+    Shard {
+        id: usize,
+        start: u128,
+        end: u128,
+    },
+    // coordinator to worker: nothing left to search
+    Stop,
+    // worker to coordinator: expressions evaluated so far in the shard
+    Progress {
+        id: usize,
+        evaluated: u64,
+    },
+    // worker to coordinator: the shard is searched
+    Done {
+        id: usize,
+        evaluated: u64,
+        pruned: u64,
+        skipped: u64,
+        cut: u64,
+        result: Option<String>,
+    },
+}
+
+#[derive(Clone, Copy)]
+struct Shard {
+    id: usize,
+    start: u128,
+    end: u128,
+}
+
+// Shared by the threads serving the workers. A shard stays in `remaining` until a worker
+// reports it done, the shard of a lost worker goes back to `queue`.
+struct Progress {
+    queue: VecDeque<Shard>,
+    total: usize,
+    remaining: usize,
+    running: HashMap<usize, u64>, // expressions evaluated so far by the shards in work
+    evaluated: u64,
+    pruned: u64,
+    skipped: u64,
+    cut: u64,
+    result: Option<String>,
+}
+
+impl Progress {
+    fn is_finished(&self) -> bool {
+        self.result.is_some() || self.remaining == 0
+    }
+
+    fn get_evaluated(&self) -> u64 {
+        self.evaluated + self.running.values().sum::<u64>()
+    }
+}
+
+// What a worker reports for a searched shard.
+struct Counters {
+    evaluated: u64,
+    pruned: u64,
+    skipped: u64,
+    cut: u64,
+    result: Option<String>,
+}
+
+type SharedProgress = Arc<(Mutex<Progress>, Condvar)>;
+
+fn send(stream: &mut TcpStream, message: &Message) -> Result<(), String> {
+    let mut line = serde_json::to_string(message).map_err(|err| err.to_string())?;
+    line.push('\n');
+    stream
+        .write_all(line.as_bytes())
+        .map_err(|err| err.to_string())
+}
+
+// Next message, `None` once the other side has closed the connection.
+fn receive(reader: &mut BufReader<TcpStream>) -> Result<Option<Message>, String> {
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Ok(0) => Ok(None),
+        Ok(_) => serde_json::from_str(&line)
+            .map(Some)
+            .map_err(|err| format!("invalid message: {}", err)),
+        Err(err)
+            if matches!(
+                err.kind(),
+                ErrorKind::ConnectionReset | ErrorKind::ConnectionAborted
+            ) =>
+        {
+            Ok(None)
+        }
+        // the read timeout of the stream has passed
+        Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {
+            Err("no message in time".to_string())
+        }
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+// Splits the schemes of the run into shards, waits for workers on `listen` and hands the
+// shards out until the number is found or every shard is searched.
+pub fn coordinate(
+    configs: &Config,
+    number: Rational,
+    listen: &str,
+    shard_size: Option<u128>,
+    verbose: bool,
+) -> Result<SearchOutcome, String> {
+    let operands = configs.get_operands();
+    let count = schemes_lib::count_schemes(operands.len().saturating_sub(1));
+    let (first, last) = match configs.get_scheme_range() {
+        Some(range) => (
+            range.start.min(count),
+            range.end.unwrap_or(count).min(count),
+        ),
+        None => (0, count),
+    };
+    let shard_size = shard_size.unwrap_or_else(|| ((last - first) / DEFAULT_SHARDS).max(1));
+
+    let mut queue = VecDeque::<Shard>::new();
+    let mut start = first;
+    while start < last {
+        let end = last.min(start.saturating_add(shard_size));
+        queue.push_back(Shard {
+            id: queue.len(),
+            start,
+            end,
+        });
+        start = end;
+    }
+    let total = queue.len();
+    let progress: SharedProgress = Arc::new((
+        Mutex::new(Progress {
+            queue,
+            total,
+            remaining: total,
+            running: HashMap::new(),
+            evaluated: 0,
+            pruned: 0,
+            skipped: 0,
+            cut: 0,
+            result: None,
+        }),
+        Condvar::new(),
+    ));
+
+    let (lower_bound, upper_bound) = configs.get_bounds();
+    let setup = Message::Setup {
+        number,
+        operands,
+        operations: configs.get_operations(),
+        lower_bound,
+        upper_bound,
+        integer_only: configs.get_arithmetic() == Arithmetic::Integer,
+        real_roots: configs.get_powers() == Powers::RealRoots,
+        concatenate_results: configs.get_concatenation() == Concatenation::Any,
+        decimal_point: configs.get_decimals() != Decimals::Off,
+        repeating_decimals: configs.get_decimals() == Decimals::Repeating,
+        canonical_trees: configs.get_trees() == Trees::Canonical,
+        interval_pruning: configs.get_pruning() == Pruning::Intervals,
+        split_depth: configs.get_split_depth(),
+    };
+    let setup = serde_json::to_string(&setup).map_err(|err| err.to_string())?;
+
+    let listener =
+        TcpListener::bind(listen).map_err(|err| format!("cannot listen on {}: {}", listen, err))?;
+    if verbose {
+        println!(
+            "Waiting for workers on {}, {} shards of schemes {}..{}",
+            listen, total, first, last
+        );
+    }
+    let accept_progress = Arc::clone(&progress);
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let worker_progress = Arc::clone(&accept_progress);
+            let setup = setup.clone();
+            thread::spawn(move || serve_worker(stream, &setup, worker_progress, verbose));
+        }
+    });
+
+    let (state, changed) = &*progress;
+    let mut state = state.lock().unwrap();
+    while !state.is_finished() {
+        state = changed.wait(state).unwrap();
+    }
+    Ok(SearchOutcome {
+        result: state.result.take(),
+        evaluated: state.evaluated,
+        pruned: state.pruned,
+        dedup_peak: 0,
+        skipped: state.skipped,
+        cut: state.cut,
+    })
+}
+
+// Gives shards to one worker until the run is finished or the worker is lost.
+fn serve_worker(mut stream: TcpStream, setup: &str, progress: SharedProgress, verbose: bool) {
+    let peer = stream
+        .peer_addr()
+        .map(|addr| addr.to_string())
+        .unwrap_or_else(|_| String::from("worker"));
+    // a worker missing three progress reports is lost, even with its connection open
+    let timeout = stream.set_read_timeout(Some(3 * PROGRESS_INTERVAL));
+    let mut reader = match timeout.and_then(|_| stream.try_clone()) {
+        Ok(stream) => BufReader::new(stream),
+        Err(_) => return,
+    };
+    if writeln!(stream, "{}", setup).is_err() {
+        return;
+    }
+    if verbose {
+        println!("{} connected", peer);
+    }
+
+    let (state, changed) = &*progress;
+    loop {
+        let shard = {
+            let mut state = state.lock().unwrap();
+            loop {
+                if state.is_finished() {
+                    let _ = send(&mut stream, &Message::Stop);
+                    return;
+                }
+                if let Some(shard) = state.queue.pop_front() {
+                    break shard;
+                }
+                // every shard is taken, one may come back from a lost worker
+                state = changed.wait(state).unwrap();
+            }
+        };
+
+        match run_shard(&mut stream, &mut reader, shard, &progress) {
+            Ok(Counters {
+                evaluated,
+                pruned,
+                skipped,
+                cut,
+                result,
+            }) => {
+                let mut state = state.lock().unwrap();
+                state.running.remove(&shard.id);
+                state.remaining -= 1;
+                state.evaluated += evaluated;
+                state.pruned += pruned;
+                state.skipped += skipped;
+                state.cut += cut;
+                if state.result.is_none() {
+                    state.result = result;
+                }
+                if verbose {
+                    println!(
+                        "{}: shard {} done, {}/{} shards, {} expressions evaluated",
+                        peer,
+                        shard.id,
+                        state.total - state.remaining,
+                        state.total,
+                        state.get_evaluated()
+                    );
+                }
+                changed.notify_all();
+            }
+            Err(err) => {
+                if verbose {
+                    println!("{} lost ({}), shard {} is reassigned", peer, err, shard.id);
+                }
+                let mut state = state.lock().unwrap();
+                state.running.remove(&shard.id);
+                state.queue.push_front(shard);
+                changed.notify_all();
+                return;
+            }
+        }
+    }
+}
+
+fn run_shard(
+    stream: &mut TcpStream,
+    reader: &mut BufReader<TcpStream>,
+    shard: Shard,
+    progress: &SharedProgress,
+) -> Result<Counters, String> {
+    send(
+        stream,
+        &Message::Shard {
+            id: shard.id,
+            start: shard.start,
+            end: shard.end,
+        },
+    )?;
+    loop {
+        match receive(reader)?.ok_or("connection closed")? {
+            Message::Progress { id, evaluated } if id == shard.id => {
+                progress.0.lock().unwrap().running.insert(id, evaluated);
+            }
+            Message::Done {
+                id,
+                evaluated,
+                pruned,
+                skipped,
+                cut,
+                result,
+            } if id == shard.id => {
+                return Ok(Counters {
+                    evaluated,
+                    pruned,
+                    skipped,
+                    cut,
+                    result,
+                })
+            }
+            message => return Err(format!("unexpected message {:?}", message)),
+        }
+    }
+}
+
+// Connects to the coordinator and searches the shards it sends with the pool strategy,
+// reporting progress every second, until the coordinator stops or closes the connection.
+pub fn work(connect: &str, verbose: bool) -> Result<(), String> {
+    let mut stream = connect_to(connect)?;
+    let mut reader = BufReader::new(stream.try_clone().map_err(|err| err.to_string())?);
+    let setup = receive(&mut reader)?.ok_or("the coordinator closed the connection")?;
+    let (number, operands, operations) = match setup {
+        Message::Setup {
+            number,
+            operands,
+            operations,
+            lower_bound,
+            upper_bound,
+            integer_only,
+            real_roots,
+            concatenate_results,
+            decimal_point,
+            repeating_decimals,
+            canonical_trees,
+            interval_pruning,
+            split_depth,
+        } => {
+            rpn_evaluator::set_bounds(lower_bound, upper_bound);
+            rpn_evaluator::set_integer_only(integer_only);
+            rpn_evaluator::set_real_roots(real_roots);
+            schemes_lib::set_concatenate_results(concatenate_results);
+            schemes_lib::set_decimal_forms(decimal_point, repeating_decimals);
+            schemes_lib::set_canonical_trees(canonical_trees);
+            generator::set_interval_pruning(interval_pruning);
+            let parallelism = *generator::PARALLELISM.read().unwrap();
+            generator::set_parallelism(generator::Parallelism {
+                split_depth,
+                ..parallelism
+            });
+            (number, operands, operations)
+        }
+        message => return Err(format!("unexpected message {:?}", message)),
+    };
+    if verbose {
+        println!(
+            "Connected to {}, searching {} over {}",
+            connect,
+            number,
+            operands.join(" ")
+        );
+    }
+
+    loop {
+        let (id, start, end) = match receive(&mut reader) {
+            Ok(Some(Message::Shard { id, start, end })) => (id, start, end),
+            // a finished coordinator may exit without stopping its workers
+            Ok(Some(Message::Stop)) | Ok(None) => return Ok(()),
+            Ok(Some(message)) => return Err(format!("unexpected message {:?}", message)),
+            Err(err) => return Err(err),
+        };
+
+        // the reporter wakes up as soon as the shard is searched, not at its next report
+        let (searched, until_searched) = mpsc::channel::<()>();
+        let reporter = {
+            let mut stream = stream.try_clone().map_err(|err| err.to_string())?;
+            thread::spawn(move || {
+                while let Err(RecvTimeoutError::Timeout) =
+                    until_searched.recv_timeout(PROGRESS_INTERVAL)
+                {
+                    let (evaluated, _) = generator::get_search_stats();
+                    if send(&mut stream, &Message::Progress { id, evaluated }).is_err() {
+                        break;
+                    }
+                }
+            })
+        };
+        let schemes = schemes_lib::generate_schemes_range(operands.clone(), start, end);
+        let result = generator::search_number(schemes, operations.clone(), number);
+        drop(searched);
+        let _ = reporter.join();
+
+        let (evaluated, pruned) = generator::get_search_stats();
+        if verbose {
+            println!(
+                "shard {} (schemes {}..{}): {} expressions evaluated{}",
+                id,
+                start,
+                end,
+                evaluated,
+                if result.is_some() { ", found" } else { "" }
+            );
+        }
+        let done = Message::Done {
+            id,
+            evaluated,
+            pruned,
+            skipped: schemes_lib::get_skipped(),
+            cut: generator::get_cut(),
+            result,
+        };
+        // a finished coordinator may exit while the shard is searched
+        if send(&mut stream, &done).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+// The coordinator may still be starting, so the connection is retried for a few seconds.
+fn connect_to(address: &str) -> Result<TcpStream, String> {
+    let mut attempt = 1;
+    loop {
+        match TcpStream::connect(address) {
+            Ok(stream) => return Ok(stream),
+            Err(err) if attempt >= CONNECT_ATTEMPTS => {
+                return Err(format!("cannot connect to {}: {}", address, err))
+            }
+            Err(_) => {
+                attempt += 1;
+                thread::sleep(CONNECT_INTERVAL);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Worker side of a connection, speaking the protocol without searching.
+    fn connect_worker(address: &str) -> (TcpStream, BufReader<TcpStream>) {
+        let stream = connect_to(address).unwrap();
+        let mut reader = BufReader::new(stream.try_clone().unwrap());
+        assert!(matches!(
+            receive(&mut reader),
+            Ok(Some(Message::Setup { .. }))
+        ));
+        (stream, reader)
+    }
+
+    #[test]
+    fn lost_worker_shard_is_reassigned() {
+        let address = TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .unwrap()
+            .to_string();
+        // 1 2 3 has two schemes, a shard each
+        let configs = Config::parse_from([
+            "crazy_representation_schemes",
+            "coordinate",
+            "-t",
+            "9",
+            "--operands",
+            "1",
+            "2",
+            "3",
+            "--listen",
+            &address,
+        ])
+        .unwrap();
+        let coordinator = {
+            let address = address.clone();
+            thread::spawn(move || {
+                coordinate(&configs, Rational::integer(9), &address, Some(1), false).unwrap()
+            })
+        };
+
+        // the first worker is lost in the middle of its shard
+        let (lost, mut lost_reader) = connect_worker(&address);
+        let lost_shard = match receive(&mut lost_reader) {
+            Ok(Some(Message::Shard { id, .. })) => id,
+            message => panic!("expected a shard, got {:?}", message),
+        };
+        drop(lost_reader);
+        drop(lost);
+
+        // the second worker keeps its connection open but never answers
+        let (_silent, mut silent_reader) = connect_worker(&address);
+        let silent_shard = match receive(&mut silent_reader) {
+            Ok(Some(Message::Shard { id, .. })) => id,
+            message => panic!("expected a shard, got {:?}", message),
+        };
+
+        // the third worker is given every shard, the lost and the silent ones included
+        let (mut stream, mut reader) = connect_worker(&address);
+        let mut searched = Vec::new();
+        loop {
+            match receive(&mut reader) {
+                Ok(Some(Message::Shard { id, .. })) => {
+                    searched.push(id);
+                    let done = Message::Done {
+                        id,
+                        evaluated: 5,
+                        pruned: 0,
+                        skipped: 0,
+                        cut: 0,
+                        result: None,
+                    };
+                    send(&mut stream, &done).unwrap();
+                }
+                Ok(Some(Message::Stop)) => break,
+                message => panic!("expected a shard or stop, got {:?}", message),
+            }
+        }
+        searched.sort_unstable();
+        assert_eq!(searched, vec![0, 1]);
+        assert!(searched.contains(&lost_shard));
+        assert!(searched.contains(&silent_shard));
+
+        let outcome = coordinator.join().unwrap();
+        assert_eq!(outcome.result, None);
+        assert_eq!(outcome.evaluated, 10);
+    }
+}
@@ -1,14 +1,19 @@
 use dashmap::DashMap;
 use std::cmp::min;
-use std::process;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Mutex, RwLock};
 use std::thread;
 use std::time::Instant;
-use threadpool::ThreadPool;
 
-use crate::rpn_evaluator;
-use crate::schemes_lib::{generate_schemes, Schema};
+use crazy_representation_core::intervals;
+use crazy_representation_core::operators::Operator;
+use crazy_representation_core::rational::Rational;
+use crazy_representation_core::residues;
+use crazy_representation_core::tasks::{self, TaskQueue};
 
+use crate::custom_writer;
+use crate::rpn_evaluator;
+use crate::schemes_lib::{self, OperatorSets, Schema, Schemes};
 
 const THREADS_COEFFICIENT_4: usize = 16; // 18 30
 const THREADS_COEFFICIENT_3: usize = 1; // 18 30
@@ -16,289 +21,251 @@ const THREADS_COEFFICIENT_2: usize = 256;
 const THREADS_COEFFICIENT: usize = 18; // 18 30
 
 const SHOULD_CHECK_UNIQUENESS: bool = true;
-const SHOULD_EXIT_PROCESS: bool = true;
 
 const SHOULD_SHOW_INTERMEDIATE: bool = false;
 
 lazy_static! {
     pub static ref BIN_OPERATIONS: RwLock<Mutex<Vec<String>>> = RwLock::new(Mutex::new(Vec::new()));
-    pub static ref NUMBER: RwLock<u64> = RwLock::new(0);
+    static ref OPERATORS: RwLock<Vec<Operator>> = RwLock::new(Vec::new()); // BIN_OPERATIONS for the interval pruning
+    pub static ref NUMBER: RwLock<Rational> = RwLock::new(Rational::ZERO);
     pub static ref RES_MAP: DashMap<String, bool> = DashMap::new(); // (calculation res, number of remaining operations)
 
-    pub static ref SCHEMES:  Mutex<Vec<Schema>> = Mutex::new(Vec::new());
-    pub static ref OPERATIONS_SET: Mutex<Vec<Vec<String>>> = Mutex::new(Vec::new());
+    pub static ref OPERANDS: RwLock<Vec<String>> = RwLock::new(Vec::new()); // schemes of the operators sets drivers
+
+    pub static ref FOUND: Mutex<Option<String>> = Mutex::new(None); // first expression found by the pool search
+    pub static ref PARALLELISM: RwLock<Parallelism> = RwLock::new(Parallelism::default());
+    static ref RESIDUES: DashMap<u64, String> = DashMap::new(); // first expression of every residue found by the modular search
 }
 
-/**************************************************************************************************************** */
-pub fn generate_threads2(operands: Vec<String>, bin_operations: Vec<String>, number: u64) {
-    let operands_ln = operands.clone().len() as u32;
+// Run options of the workers, the defaults are derived from the amount of work when unset.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Parallelism {
+    pub threads: Option<usize>, // overrides the threads coefficients
+    pub split_depth: Option<usize>,
+    pub queue_size: Option<usize>,
+}
 
-    {
-        *BIN_OPERATIONS.write().unwrap().lock().unwrap() = bin_operations;
+static STOP: AtomicBool = AtomicBool::new(false);
+static EVALUATED: AtomicU64 = AtomicU64::new(0); // complete expressions evaluated by the current search
+static PRUNED: AtomicU64 = AtomicU64::new(0); // branches skipped by the uniqueness check
+static INTERVAL_PRUNING: AtomicBool = AtomicBool::new(true);
+static CUT: AtomicU64 = AtomicU64::new(0); // branches whose interval of values misses the number
+static MODULUS: AtomicU64 = AtomicU64::new(1);
+static RESIDUE: AtomicU64 = AtomicU64::new(u64::MAX); // residue searched for, u64::MAX for all of them
+static RESIDUES_LEFT: AtomicU64 = AtomicU64::new(0); // residues the modular search still looks for
+
+// (evaluated expressions, pruned branches) of the last search
+pub fn get_search_stats() -> (u64, u64) {
+    (
+        EVALUATED.load(Ordering::Relaxed),
+        PRUNED.load(Ordering::Relaxed),
+    )
+}
 
-        let mut rw_n = NUMBER.write().unwrap();
-        *rw_n = number;
-    }
+// Entries of the uniqueness table. The table only grows during a search and is
+// cleared before the next one, so after a search this is its peak size.
+pub fn get_dedup_size() -> usize {
+    RES_MAP.len()
+}
 
-    let scheme_generator_th = thread::spawn(move || {
-        generate_schemes2(operands);
-    });
+// Branches of the last pool search cut by the interval pruning.
+pub fn get_cut() -> u64 {
+    CUT.load(Ordering::Relaxed)
+}
 
-    let operations_configs_generator_th = thread::spawn(move || {
-        generate_operations(operands_ln as i32 - 1);
-    });
-    let start = Instant::now();
-    println!("Start generate schemes Time {}s", start.elapsed().as_secs());
-    for thrd in [scheme_generator_th, operations_configs_generator_th] {
-        thrd.join();
-    }
-    // scheme_generator_th.join();
-    // operations_configs_generator_th.join();
-    println!("Finish generate schemes Time {}s", start.elapsed().as_secs());
-    println!();
-    spawn_threads4();
-}
-
-pub fn generate_schemes2(operands: Vec<String>) {
-    produce_schema(
-        (operands.len() - 1) as i32,
-        (operands.len() - 1) as i32,
-        0,
-        Vec::<i32>::new(),
-    );
-    for result in SCHEMES.lock().unwrap().iter_mut() {
-        result.set_result_vec(operands.clone());
-    }
+pub fn set_interval_pruning(interval_pruning: bool) {
+    INTERVAL_PRUNING.store(interval_pruning, Ordering::Relaxed);
 }
 
-fn produce_schema(available: i32, max_ops: i32, step: i32, positions: Vec<i32>) {
-    if available == 0 && step == (max_ops + 1) {
-        SCHEMES.lock().unwrap().push(Schema::from_positions(
-            positions.into_iter().rev().collect(),
-        ));
-        return;
-    }
+pub fn set_parallelism(parallelism: Parallelism) {
+    *PARALLELISM.write().unwrap() = parallelism;
+}
 
-    if step < max_ops + 1 {
-        produce_schema(available, max_ops, step + 1, positions.clone());
+// Workers for `tasks` tasks: the cpus times the coefficient of the driver, but never more
+// workers than tasks, so short operand lists do not start idle threads.
+fn threads_number(coefficient: usize, tasks: usize) -> usize {
+    match PARALLELISM.read().unwrap().threads {
+        Some(threads) => threads,
+        None => min(num_cpus::get() * coefficient, tasks).max(1),
     }
+}
+
+// Queue of a thread pool search over the schemes and the number of operators placed in
+// a schema before it becomes a task. Unless set, the depth is the smallest one giving
+// every worker a few tasks: 0 for long operand lists, more for short ones.
+fn schemes_queue(schemes: &Schemes, coefficient: usize) -> (TaskQueue, usize) {
+    let parallelism = *PARALLELISM.read().unwrap();
+    let operations = BIN_OPERATIONS.read().unwrap().lock().unwrap().len().max(1);
+    let max_depth = schemes.max_operators;
+    let schemes_count = count_schemes(schemes);
+    let wanted_tasks = 4 * parallelism
+        .threads
+        .unwrap_or_else(|| num_cpus::get() * coefficient);
+
+    let split_depth = match parallelism.split_depth {
+        Some(depth) => min(depth, max_depth),
+        None => {
+            let mut depth = 0;
+            let mut tasks = schemes_count;
+            while tasks < wanted_tasks && depth < max_depth {
+                depth += 1;
+                tasks = tasks.saturating_mul(operations);
+            }
+            depth
+        }
+    };
+    let tasks = schemes_count.saturating_mul(operations.saturating_pow(split_depth as u32));
+    (task_queue(threads_number(coefficient, tasks)), split_depth)
+}
 
-    let can_use = min(available, step - 2 - (max_ops - 1 - available));
-    if can_use > 0 {
-        let mut new_positions = positions;
-        new_positions.push(step + (max_ops - available));
-        produce_schema(available - 1, max_ops, step, new_positions);
+fn count_tasks(count: u128) -> usize {
+    min(count, usize::MAX as u128) as usize
+}
+
+// Schemes a search goes through, fewer than all of them for a range of schemes, each one
+// counted with the forms of its operands.
+fn count_schemes(schemes: &Schemes) -> usize {
+    let all = count_tasks(schemes_lib::count_schemes(schemes.max_operators))
+        .saturating_mul(schemes.forms);
+    match schemes.size_hint().1 {
+        Some(upper) => min(upper, all),
+        None => all,
     }
 }
 
-fn generate_operations(ops_number: i32) {
-    generate_op_set(ops_number, Vec::<String>::new());
+// Schemes and operators sets over the operands for the drivers checking every operators
+// set against every schema. Both are enumerated lazily, only their numbers are returned.
+fn prepare_operation_sets(operands: Vec<String>) -> (usize, usize) {
+    let operators = operands.len().saturating_sub(1);
+    let operations = BIN_OPERATIONS.read().unwrap().lock().unwrap().len();
+    *OPERANDS.write().unwrap() = operands;
+    (
+        count_tasks(schemes_lib::count_schemes(operators)),
+        operations.saturating_pow(operators as u32),
+    )
 }
 
-fn generate_op_set(ops_number: i32, ops_vec: Vec<String>) {
-    if ops_number == 0 {
-        OPERATIONS_SET.lock().unwrap().push(ops_vec.clone());
-    } else {
-        let ops: Vec<String>;
-        {
-            ops = BIN_OPERATIONS.read().unwrap().lock().unwrap().to_vec();
-        }
+fn operation_sets() -> OperatorSets {
+    let operations = BIN_OPERATIONS.read().unwrap().lock().unwrap().to_vec();
+    let operators = OPERANDS.read().unwrap().len().saturating_sub(1);
+    OperatorSets::new(operations, operators)
+}
 
-        for operation in ops.iter() {
-            let mut new_vec = ops_vec.clone();
-            new_vec.push(operation.clone());
-            generate_op_set(ops_number - 1, new_vec);
+fn task_queue(threads: usize) -> TaskQueue {
+    let queue_size = PARALLELISM
+        .read()
+        .unwrap()
+        .queue_size
+        .unwrap_or_else(|| tasks::default_queue_size(threads));
+    TaskQueue::new(threads, queue_size)
+}
+
+// Places `depth` operators in the schema in every possible way and queues the task
+// for each of the resulting schemes.
+fn split_schema(queue: &TaskQueue, schema: Schema, depth: usize, task: fn(Schema)) {
+    if STOP.load(Ordering::Relaxed) {
+        return;
+    }
+    if depth == 0 || schema.get_number_of_remain_positions() == 0 {
+        queue.execute(move || task(schema));
+    } else {
+        let operations = BIN_OPERATIONS.read().unwrap().lock().unwrap().to_vec();
+        for operation in operations {
+            let mut copy_schema = Schema::from_schema(&schema);
+            if copy_schema.insert_operator(operation) {
+                split_schema(queue, copy_schema, depth - 1, task);
+            }
         }
     }
 }
 
-fn spawn_threads2() {
-    let ln = SCHEMES.lock().unwrap().len() * OPERATIONS_SET.lock().unwrap().len();
-    let thread_bundle_size = (num_cpus::get() * THREADS_COEFFICIENT_2) as u32;
-    println!("Number of threads: {}", ln);
-    println!("Number of threads in bundle: {}", thread_bundle_size);
-    println!("Number of bundles: {}", ln as u32 / thread_bundle_size);
-    drop(ln);
+/**************************************************************************************************************** */
+// Thread per schema and operators set, threads are started in bundles
+// and every bundle is joined before the next one starts.
+pub fn search_number_by_thread_bundles(
+    operands: Vec<String>,
+    bin_operations: Vec<String>,
+    number: Rational,
+) -> Option<String> {
+    prepare_search(bin_operations, number);
+    let (schemes_count, operation_sets_count) = prepare_operation_sets(operands.clone());
 
-    let mut thrds: Vec<_> = Vec::new();
-    let mut i = 0;
-    let mut j = 0;
     let start = Instant::now();
+    let thread_bundle_size = threads_number(
+        THREADS_COEFFICIENT_2,
+        schemes_count.saturating_mul(operation_sets_count),
+    );
 
-    let schemes = &SCHEMES.lock().unwrap().clone();
-    let operations_set = &OPERATIONS_SET.lock().unwrap().clone();
-
-    // let schemes: Vec<Schema>;
-    // let operations_set : Vec<Vec<String>>;
-    // {
-    //     schemes = SCHEMES.lock().unwrap().clone().to_vec();
-    //     operations_set = OPERATIONS_SET.lock().unwrap().clone().to_vec();
-    // }
-
-    for schema in schemes {
-        for op_set in operations_set {
+    let mut thrds: Vec<_> = Vec::new();
+    'bundles: for schema in schemes_lib::generate_schemes(operands) {
+        for op_set in operation_sets() {
+            if STOP.load(Ordering::Relaxed) {
+                break 'bundles;
+            }
             let copy_schema = Schema::from_schema(&schema);
-            let copy_op_set = op_set.clone();
+            let copy_op_set = op_set;
             thrds.push(thread::spawn(move || {
                 check_and_evaluate(copy_schema, copy_op_set, start);
             }));
-            i += 1;
-            if i == thread_bundle_size {
-                i = 0;
-                j += 1;
-                println!(
-                    "Start Threads Bundle #{}\t Time: {}s",
-                    j,
-                    start.elapsed().as_secs()
-                );
-                for thrd in thrds {
-                    let _ = thrd.join().unwrap();
-                }
-                println!(
-                    "Finish Threads Bundle #{}\t Time: {}s",
-                    j,
-                    start.elapsed().as_secs()
-                );
-                thrds = Vec::new();
+            if thrds.len() == thread_bundle_size {
+                join_bundle(&mut thrds);
             }
         }
     }
-    println!(
-        "Start Last Threads Bundle \t Time: {}s",
-        start.elapsed().as_secs()
-    );
-    for thrd in thrds {
-        let _ = thrd.join().unwrap();
-    }
-    println!(
-        "Finish Last Threads Bundle \t Time: {}s",
-        start.elapsed().as_secs()
-    );
-    println!("Unreachable!")
+    join_bundle(&mut thrds);
+    FOUND.lock().unwrap().take()
 }
 
-fn spawn_threads3() {
-    let ln = OPERATIONS_SET.lock().unwrap().len();
-    let thread_bundle_size = (num_cpus::get() * THREADS_COEFFICIENT_2) as u32;
-    println!("Number of operations: {}", ln);
-    println!("Number of threads in bundle: {}", thread_bundle_size);
-    println!("Number of bundles: {}", ln as u32 / thread_bundle_size);
-    println!("Threads coefficient: {}", THREADS_COEFFICIENT_2);
-    println!();
-    drop(ln);
+// Thread per operators set checked against every schema, started in bundles.
+pub fn search_number_by_operation_bundles(
+    operands: Vec<String>,
+    bin_operations: Vec<String>,
+    number: Rational,
+) -> Option<String> {
+    prepare_search(bin_operations, number);
+    let (_, operation_sets_count) = prepare_operation_sets(operands);
 
-    let mut thrds: Vec<_> = Vec::new();
-    let mut i = 0;
-    let mut j = 0;
     let start = Instant::now();
+    let thread_bundle_size = threads_number(THREADS_COEFFICIENT_2, operation_sets_count);
 
-    // let schemes = &SCHEMES.lock().unwrap().clone();
-    let operations_set = &OPERATIONS_SET.lock().unwrap().clone();
-
-    // let schemes: Vec<Schema>;
-    // let operations_set : Vec<Vec<String>>;
-    // {
-    //     schemes = SCHEMES.lock().unwrap().clone().to_vec();
-    //     operations_set = OPERATIONS_SET.lock().unwrap().clone().to_vec();
-    // }
-
-
-    for op_s in operations_set {
-        let copy_op_s = op_s.clone();
+    let mut thrds: Vec<_> = Vec::new();
+    for op_s in operation_sets() {
+        if STOP.load(Ordering::Relaxed) {
+            break;
+        }
         thrds.push(thread::spawn(move || {
-            check_op_s(copy_op_s, start);
+            check_op_s(op_s, start);
         }));
-        i += 1;
-        if i == thread_bundle_size {
-            i = 0;
-            j += 1;
-            let st = start.elapsed().as_secs();
-            println!(
-                "Start Threads Bundle #{}\t Time: {}s",
-                j,
-                start.elapsed().as_secs()
-            );
-            for thrd in thrds {
-                let _ = thrd.join().unwrap();
-            }
-
-            let st2 = start.elapsed().as_secs();
-            let mut threads_per_sec: u64;
-            if st == st2{
-                threads_per_sec = 0;
-            }else{
-                threads_per_sec = thread_bundle_size as u64 / ( st2 - st);
-            }
-
-            println!(
-                "Finish Threads Bundle #{}\t Time: {}s Threads per sec: {}",
-                j,
-                start.elapsed().as_secs(),
-                threads_per_sec
-            );
-            thrds = Vec::new();
+        if thrds.len() == thread_bundle_size {
+            join_bundle(&mut thrds);
         }
     }
-    println!(
-        "Start Last Threads Bundle \t Time: {}s",
-        start.elapsed().as_secs()
-    );
-    for thrd in thrds {
-        let _ = thrd.join().unwrap();
-    }
-    println!(
-        "Finish Last Threads Bundle \t Time: {}s",
-        start.elapsed().as_secs()
-    );
-    println!("Number {} is UNREACHABLE!", NUMBER.read().unwrap())
+    join_bundle(&mut thrds);
+    FOUND.lock().unwrap().take()
 }
 
-fn spawn_threads4() {
-    let ln = OPERATIONS_SET.lock().unwrap().len();
-    let thread_bundle_size = (num_cpus::get() * THREADS_COEFFICIENT_3) as u32;
-    println!("Number of operations: {}", ln);
-    println!("Number of threads in bundle: {}", thread_bundle_size);
-    println!("Number of bundles: {}", ln as u32 / thread_bundle_size);
-    println!("Threads coefficient: {}", THREADS_COEFFICIENT_3);
-    println!();
-    drop(ln);
-
-    let start = Instant::now();
-
-    let operations_set = &OPERATIONS_SET.lock().unwrap().clone();
-
-    let pool = ThreadPool::new(thread_bundle_size as usize);
-
-    for op_s in operations_set {
-        let copy_op_s = op_s.clone();
-        pool.execute(move || {
-            check_op_s(copy_op_s, start);
-        });
+fn join_bundle(thrds: &mut Vec<thread::JoinHandle<()>>) {
+    for thrd in thrds.drain(..) {
+        thrd.join().unwrap();
     }
-    println!("Start executing Time: {}s", start.elapsed().as_secs());
-    pool.join();
-    println!("Number {} is UNREACHABLE! Total time: {}s", NUMBER.read().unwrap(), start.elapsed().as_secs());
 }
 
 fn check_op_s(op_s: Vec<String>,  start: Instant){
-    let schemes = &SCHEMES.lock().unwrap().clone();
-    for schema in schemes{
+    let operands = OPERANDS.read().unwrap().clone();
+    for schema in schemes_lib::generate_schemes(operands) {
+        if STOP.load(Ordering::Relaxed) {
+            return;
+        }
         let copy_op_s = op_s.clone();
-        let copy_schema = schema.clone();
-        check_and_evaluate(copy_schema, copy_op_s, start);
+        check_and_evaluate(schema, copy_op_s, start);
     }
 }
 
-fn check_schema(schema: Schema,  start: Instant){
-    let operations_set = &OPERATIONS_SET.lock().unwrap().clone();
-    for op_s in operations_set{
-        let copy_op_s = op_s.clone();
-        let copy_schema = schema.clone();
-        check_and_evaluate(copy_schema, copy_op_s, start);
+fn check_and_evaluate(mut schema: Schema, op_set: Vec<String>, _start: Instant) {
+    if STOP.load(Ordering::Relaxed) {
+        return;
     }
-}
 
-fn check_and_evaluate(mut schema: Schema, op_set: Vec<String>, start: Instant) {
     for operator in op_set {
         if !SHOULD_CHECK_UNIQUENESS || check_uniqueness(&schema) {
             match schema.insert_operator(operator) {
@@ -317,31 +284,15 @@ fn check_and_evaluate(mut schema: Schema, op_set: Vec<String>, start: Instant) {
             println!("{}", result);
         }
 
+        EVALUATED.fetch_add(1, Ordering::Relaxed);
         match rpn_evaluator::evaluate(result) {
             Ok(calculated) => {
-                if calculated == *number as f64 {
-                    println!(
-                        "\n{}\n{}\n",
-                        result,
-                        match rpn_evaluator::to_infix(result) {
-                            Ok(x) => x,
-                            Err(err) => err,
-                        }
-                    );
-                    println!(
-                        "Finish for number {}\nTotal Time: {}s",
-                        number,
-                        start.elapsed().as_secs()
-                    );
-                    if SHOULD_EXIT_PROCESS {
-                        process::exit(0);
-                    }
+                if number.matches(result, calculated) {
+                    record_found(result);
                 }
             }
             Err(error) => {
-                if !(error == "Number is too big or too small"
-                    || error == "Concatenation is unreal")
-                {
+                if error.is_malformed() {
                     println!("\nERROR: {}\n", error);
                 }
             }
@@ -349,83 +300,220 @@ fn check_and_evaluate(mut schema: Schema, op_set: Vec<String>, start: Instant) {
     }
 }
 
+// Keeps the first expression found and stops the other workers of the search.
+fn record_found(result: &str) {
+    let mut found = FOUND.lock().unwrap();
+    if found.is_none() {
+        *found = Some(result.to_string());
+    }
+    STOP.store(true, Ordering::Relaxed);
+}
+
 /*********************************************************************************************** */
 
-pub fn generate_threads(schemes: Vec<Schema>, bin_operations: Vec<String>, number: u64) {
-    let ln = bin_operations.len();
-    {
-        *BIN_OPERATIONS.write().unwrap().lock().unwrap() = bin_operations;
+// Searches for `number` with the pool strategy and returns the first RPN expression found
+// instead of exiting the process, so several searches can run one after another.
+pub fn search_number(
+    schemes: Schemes,
+    bin_operations: Vec<String>,
+    number: Rational,
+) -> Option<String> {
+    prepare_search(bin_operations, number);
+    run_pool(schemes);
+    FOUND.lock().unwrap().take()
+}
 
-        let mut rw_n = NUMBER.write().unwrap();
-        *rw_n = number;
+// Same as `search_number`, but with a thread pool task per operators set
+// which is checked against every schema.
+pub fn search_number_by_operation_sets(
+    operands: Vec<String>,
+    bin_operations: Vec<String>,
+    number: Rational,
+) -> Option<String> {
+    prepare_search(bin_operations, number);
+    let (_, operation_sets_count) = prepare_operation_sets(operands);
+
+    let start = Instant::now();
+    let queue = task_queue(threads_number(THREADS_COEFFICIENT_3, operation_sets_count));
+    for op_s in operation_sets() {
+        if STOP.load(Ordering::Relaxed) {
+            break;
+        }
+        queue.execute(move || {
+            check_op_s(op_s, start);
+        });
     }
+    queue.join();
+    FOUND.lock().unwrap().take()
+}
+
+// Evaluates every expression over the schemes and writes the reachable numbers
+// as "number = expression" lines to the file chosen in `custom_writer`.
+pub fn enumerate_expressions(schemes: Schemes, bin_operations: Vec<String>) {
+    prepare_search(bin_operations, Rational::ZERO);
 
-    // let mut snapshots: Vec<_> = Vec::new();
-    // generate_snapshots(&mut snapshots, &schemes, ln);
-    // spawn_threads(snapshots);
-    spawn_threads_pool(schemes);
+    let (queue, split_depth) = schemes_queue(&schemes, THREADS_COEFFICIENT_4);
+    for schema in schemes {
+        split_schema(&queue, schema, split_depth, generate_expression_enumerate);
+    }
+    queue.join();
 }
 
-fn generate_snapshots(snapshots: &mut Vec<(Schema, u16, bool)>, schemes: &Vec<Schema>, ln: usize) {
+fn generate_expression_enumerate(schema: Schema) {
+    if schema.get_number_of_remain_positions() == 0 {
+        let result = &schema.get_string_result();
+        EVALUATED.fetch_add(1, Ordering::Relaxed);
+        if let Ok(calculated) = rpn_evaluator::evaluate(result) {
+            // the reachable numbers listed are the positive integers
+            if calculated > 0.0 && calculated < 9223372036854775807.0 && calculated.fract() == 0.0 {
+                let number = Rational::integer(calculated as i128);
+                if let Err(err) = custom_writer::write_to_file(&number, result) {
+                    println!("\nERROR: {}\n", err);
+                }
+            }
+        }
+    } else {
+        let operations = BIN_OPERATIONS.read().unwrap().lock().unwrap().to_vec();
+        for operation in operations {
+            let mut copy_schema = Schema::from_schema(&schema);
+            if copy_schema.insert_operator(operation) {
+                generate_expression_enumerate(copy_schema);
+            }
+        }
+    }
+}
+
+// Evaluates the expressions over the schemes modulo `modulus` and returns the first
+// expression found for every residue, ordered by residue. With a residue only that one
+// is searched for, otherwise the search stops once every residue is found.
+pub fn search_residues(
+    schemes: Schemes,
+    bin_operations: Vec<String>,
+    modulus: u64,
+    residue: Option<u64>,
+) -> Vec<(u64, String)> {
+    prepare_search(bin_operations, Rational::ZERO);
+    RESIDUES.clear();
+    MODULUS.store(modulus, Ordering::SeqCst);
+    RESIDUE.store(residue.unwrap_or(u64::MAX), Ordering::SeqCst);
+    RESIDUES_LEFT.store(residue.map_or(modulus, |_| 1), Ordering::SeqCst);
+
+    let (queue, split_depth) = schemes_queue(&schemes, THREADS_COEFFICIENT_4);
     for schema in schemes {
-        for op_idx in 0..ln {
-            let copy_schema = Schema::from_schema(&schema);
+        if STOP.load(Ordering::Relaxed) {
+            break;
+        }
+        split_schema(&queue, schema, split_depth, generate_expression_residues);
+    }
+    queue.join();
+
+    let mut found: Vec<(u64, String)> = RESIDUES
+        .iter()
+        .map(|entry| (*entry.key(), entry.value().clone()))
+        .collect();
+    found.sort_unstable_by_key(|(residue, _)| *residue);
+    RESIDUES.clear();
+    found
+}
+
+fn generate_expression_residues(schema: Schema) {
+    if STOP.load(Ordering::Relaxed) {
+        return;
+    }
+    if schema.get_number_of_remain_positions() == 0 {
+        let result = schema.get_string_result();
+        EVALUATED.fetch_add(1, Ordering::Relaxed);
+        if let Ok(residue) = residues::evaluate_modulo(&result, MODULUS.load(Ordering::Relaxed)) {
+            record_residue(residue, result);
+        }
+    } else {
+        let operations = BIN_OPERATIONS.read().unwrap().lock().unwrap().to_vec();
+        for operation in operations {
+            let mut copy_schema = Schema::from_schema(&schema);
+            if copy_schema.insert_operator(operation) {
+                generate_expression_residues(copy_schema);
+            }
+        }
+    }
+}
 
-            snapshots.push((copy_schema, op_idx as u16, false));
+// Keeps the first expression of the residue, stops the search once nothing is left to find.
+fn record_residue(residue: u64, result: String) {
+    let wanted = RESIDUE.load(Ordering::Relaxed);
+    if (wanted != u64::MAX && wanted != residue) || RESIDUES.contains_key(&residue) {
+        return;
+    }
+    if let dashmap::mapref::entry::Entry::Vacant(entry) = RESIDUES.entry(residue) {
+        entry.insert(result.trim().to_string());
+        if RESIDUES_LEFT.fetch_sub(1, Ordering::SeqCst) == 1 {
+            STOP.store(true, Ordering::Relaxed);
         }
     }
 }
 
-fn spawn_threads(args: Vec<(Schema, u16, bool)>) {
-    let bundle_size = (num_cpus::get() * THREADS_COEFFICIENT) as u32;
-    println!("Number of tasks: {}", args.len());
-    println!("Number of threads: {}", bundle_size);
-    println!("Number of bundles: {}", args.len() as u32 / bundle_size);
+fn prepare_search(bin_operations: Vec<String>, number: Rational) {
+    {
+        *OPERATORS.write().unwrap() = bin_operations
+            .iter()
+            .filter_map(|x| Operator::from_symbol(x))
+            .collect();
+        *BIN_OPERATIONS.write().unwrap().lock().unwrap() = bin_operations;
+
+        let mut rw_n = NUMBER.write().unwrap();
+        *rw_n = number;
 
-    let mut thrds: Vec<_> = Vec::new();
-    let mut i = 0;
-    let mut j = 0;
-    let start = Instant::now();
+        *FOUND.lock().unwrap() = None;
+    }
+    // uniqueness keys of an interrupted search do not mean their subtrees were fully checked
+    RES_MAP.clear();
+    STOP.store(false, Ordering::SeqCst);
+    EVALUATED.store(0, Ordering::SeqCst);
+    PRUNED.store(0, Ordering::SeqCst);
+    CUT.store(0, Ordering::SeqCst);
+    schemes_lib::reset_skipped();
+}
+
+fn generate_snapshots(schemes: Schemes, ln: usize) -> impl Iterator<Item = (Schema, u16, bool)> {
+    schemes.flat_map(move |schema| {
+        (0..ln).map(move |op_idx| (Schema::from_schema(&schema), op_idx as u16, false))
+    })
+}
+
+// Thread per schema and last operator, see `generate_expression`, started in bundles.
+pub fn search_number_by_snapshots(
+    schemes: Schemes,
+    bin_operations: Vec<String>,
+    number: Rational,
+) -> Option<String> {
+    let ln = bin_operations.len();
+    prepare_search(bin_operations, number);
 
-    for (schema, op_idx, once_used) in args {
+    let snapshots_count = count_schemes(&schemes).saturating_mul(ln);
+    let bundle_size = threads_number(THREADS_COEFFICIENT, snapshots_count);
+    let snapshots = generate_snapshots(schemes, ln);
+    let mut thrds: Vec<_> = Vec::new();
+    for (schema, op_idx, once_used) in snapshots {
+        if STOP.load(Ordering::Relaxed) {
+            break;
+        }
         thrds.push(thread::spawn(move || {
-            generate_expression(schema, op_idx, once_used, start);
+            generate_expression(schema, op_idx, once_used);
         }));
-        i += 1;
-        if i == bundle_size {
-            i = 0;
-            j += 1;
-            println!(
-                "Start Threads Bundle #{}\t Time: {}s",
-                j,
-                start.elapsed().as_secs()
-            );
-            for thrd in thrds {
-                let _ = thrd.join().unwrap();
-            }
-            println!(
-                "Finish Threads Bundle #{}\t Time: {}s",
-                j,
-                start.elapsed().as_secs()
-            );
-            thrds = Vec::new();
+        if thrds.len() == bundle_size {
+            join_bundle(&mut thrds);
         }
     }
-    println!(
-        "Start Last Threads Bundle \t Time: {}s",
-        start.elapsed().as_secs()
-    );
-    for thrd in thrds {
-        let _ = thrd.join().unwrap();
-    }
-    println!(
-        "Finish Last Threads Bundle \t Time: {}s",
-        start.elapsed().as_secs()
-    );
-    println!("Unreachable!")
+    join_bundle(&mut thrds);
+    FOUND.lock().unwrap().take()
 }
 
-fn generate_expression(schema: Schema, op_idx: u16, once_used: bool, start: Instant) {
+// Expressions whose operators all come before `op_idx` in the operations list,
+// with the operator at `op_idx` used at least once.
+fn generate_expression(schema: Schema, op_idx: u16, once_used: bool) {
+    if STOP.load(Ordering::Relaxed) {
+        return;
+    }
+
     let operations = &BIN_OPERATIONS.read().unwrap();
     let number = NUMBER.read().unwrap();
     let remain_operations = schema.get_number_of_remain_positions();
@@ -437,46 +525,34 @@ fn generate_expression(schema: Schema, op_idx: u16, once_used: bool, start: Inst
                 println!("{}", result);
             }
 
+            EVALUATED.fetch_add(1, Ordering::Relaxed);
             match rpn_evaluator::evaluate(result) {
                 Ok(calculated) => {
-                    if calculated == *number as f64 {
-                        println!(
-                            "\n{}\n{}\n",
-                            result,
-                            match rpn_evaluator::to_infix(result) {
-                                Ok(x) => x,
-                                Err(err) => err,
-                            }
-                        );
-                        println!("Finish\nTotal Time: {}s", start.elapsed().as_secs());
-                        if SHOULD_EXIT_PROCESS {
-                            process::exit(0);
-                        }
+                    if number.matches(result, calculated) {
+                        record_found(result);
                     }
                 }
                 Err(error) => {
-                    if !(error == "Number is too big or too small"
-                        || error == "Concatenation is unreal")
-                    {
+                    if error.is_malformed() {
                         println!("\nERROR: {}\n", error);
                     }
                 }
             }
-        } else if remain_operations == 1 && once_used == false {
+        } else if remain_operations == 1 && !once_used {
             let mut copy_schema = Schema::from_schema(&schema);
             if copy_schema.insert_operator(operations.lock().unwrap()[op_idx as usize].clone()) {
-                generate_expression(copy_schema, op_idx, true, start);
+                generate_expression(copy_schema, op_idx, true);
             }
         } else {
             for idx in 0..op_idx {
                 let mut copy_schema = Schema::from_schema(&schema);
                 if copy_schema.insert_operator(operations.lock().unwrap()[idx as usize].clone()) {
-                    generate_expression(copy_schema, op_idx, once_used, start);
+                    generate_expression(copy_schema, op_idx, once_used);
                 }
             }
             let mut copy_schema = Schema::from_schema(&schema);
             if copy_schema.insert_operator(operations.lock().unwrap()[op_idx as usize].clone()) {
-                generate_expression(copy_schema, op_idx, true, start);
+                generate_expression(copy_schema, op_idx, true);
             }
         }
     }
@@ -504,6 +580,7 @@ fn check_uniqueness(schema: &Schema) -> bool {
                     }
                     Some(_) => {
                         // println!("{}", result);
+                        PRUNED.fetch_add(1, Ordering::Relaxed);
                         false
                     }
                 }
@@ -515,25 +592,33 @@ fn check_uniqueness(schema: &Schema) -> bool {
     }
 }
 
-fn spawn_threads_pool(schemes: Vec<Schema>) {
-    let start = Instant::now();
-    let pool = ThreadPool::new(num_cpus::get()*THREADS_COEFFICIENT_4);
+fn run_pool(schemes: Schemes) {
+    let (queue, split_depth) = schemes_queue(&schemes, THREADS_COEFFICIENT_4);
     for schema in schemes {
-        pool.execute(move || {
-            generate_expression_pool(schema, start);
-        });
+        split_schema(&queue, schema, split_depth, generate_expression_pool);
     }
-    pool.join();
-
-    println!("Number {} is UNREACHABLE\nTotal time: {}s\n",  NUMBER.read().unwrap(), start.elapsed().as_secs())
+    queue.join();
 }
 
-fn generate_expression_pool(schema: Schema, start: Instant) {
+fn generate_expression_pool(schema: Schema) {
+    if STOP.load(Ordering::Relaxed) {
+        return;
+    }
+
     let operations = &BIN_OPERATIONS.read().unwrap();
     let number = NUMBER.read().unwrap();
     let remain_operations = schema.get_number_of_remain_positions();
     let op_idx =  BIN_OPERATIONS.read().unwrap().lock().unwrap().len();
 
+    // checked before the uniqueness check, so a cut branch leaves no uniqueness key
+    if remain_operations > 0
+        && INTERVAL_PRUNING.load(Ordering::Relaxed)
+        && !can_reach(&schema, &OPERATORS.read().unwrap(), *number)
+    {
+        CUT.fetch_add(1, Ordering::Relaxed);
+        return;
+    }
+
     if !SHOULD_CHECK_UNIQUENESS || check_uniqueness(&schema) {
         if remain_operations == 0 {
             let result = &schema.get_string_result();
@@ -541,27 +626,15 @@ fn generate_expression_pool(schema: Schema, start: Instant) {
                 println!("{}", result);
             }
 
+            EVALUATED.fetch_add(1, Ordering::Relaxed);
             match rpn_evaluator::evaluate(result) {
                 Ok(calculated) => {
-                    if calculated == *number as f64 {
-                        println!(
-                            "\n{}\n{}\n",
-                            result,
-                            match rpn_evaluator::to_infix(result) {
-                                Ok(x) => x,
-                                Err(err) => err,
-                            }
-                        );
-                        println!("Finish\nTotal Time: {}s", start.elapsed().as_secs());
-                        if SHOULD_EXIT_PROCESS {
-                            process::exit(0);
-                        }
+                    if number.matches(result, calculated) {
+                        record_found(result);
                     }
                 }
                 Err(error) => {
-                    if !(error == "Number is too big or too small"
-                        || error == "Concatenation is unreal")
-                    {
+                    if error.is_malformed() {
                         println!("\nERROR: {}\n", error);
                     }
                 }
@@ -569,11 +642,30 @@ fn generate_expression_pool(schema: Schema, start: Instant) {
         } else {
             for idx in 0..op_idx {
                 let mut copy_schema = Schema::from_schema(&schema);
-                if copy_schema.insert_operator(operations.lock().unwrap()[idx as usize].clone()) {
-                    generate_expression_pool(copy_schema, start);
+                if copy_schema.insert_operator(operations.lock().unwrap()[idx].clone()) {
+                    generate_expression_pool(copy_schema);
                 }
             }
         }
     }
 }
 
+// Whether the interval of values the schema can still take with the operations at its
+// remaining positions meets the float values an expression equal to the number may have.
+fn can_reach(schema: &Schema, operators: &[Operator], number: Rational) -> bool {
+    let remaining = &schema.positions[..schema.get_number_of_remain_positions() as usize];
+    let mut known = schema.result_vec.iter();
+    let tokens: Vec<Option<&str>> = (0..(schema.result_vec.len() + remaining.len()) as i32)
+        .map(|idx| match remaining.contains(&idx) {
+            true => None,
+            false => known.next().map(|x| x.as_str()),
+        })
+        .collect();
+    match intervals::reachable(&tokens, operators) {
+        Some(interval) => {
+            let (low, high) = number.float_range();
+            interval.meets(low, high)
+        }
+        None => false,
+    }
+}
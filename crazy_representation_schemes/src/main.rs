@@ -3,31 +3,497 @@ extern crate lazy_static;
 extern crate num_cpus;
 extern crate dashmap;
 
-mod configs_lib;
+use std::process;
+use std::time::Instant;
+
+mod benchmark;
 mod custom_writer;
+mod distributed;
 mod generator;
-mod rpn_evaluator;
+mod report;
 mod schemes_lib;
+mod strategies;
+
+use configs_lib::{
+    Arithmetic, Concatenation, Config, Decimals, Format, Mode, Permutations, Powers, Pruning, Trees,
+};
+use crazy_representation_core::rational::Rational;
+use crazy_representation_core::residues::ResidueAnalysis;
+use crazy_representation_core::{configs_lib, rpn_evaluator};
+use report::Record;
 
 fn main() {
-    //custom_writer::create_file_to_write("output.txt");
+    let configs = match Config::from_args() {
+        Ok(configs) => configs,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(2);
+        }
+    };
 
-    let configs = configs_lib::Config::new();
+    let (lower_bound, upper_bound) = configs.get_bounds();
+    rpn_evaluator::set_bounds(lower_bound, upper_bound);
+    rpn_evaluator::set_integer_only(configs.get_arithmetic() == Arithmetic::Integer);
+    rpn_evaluator::set_real_roots(configs.get_powers() == Powers::RealRoots);
+    generator::set_parallelism(generator::Parallelism {
+        threads: configs.get_threads(),
+        split_depth: configs.get_split_depth(),
+        queue_size: configs.get_queue_size(),
+    });
+    schemes_lib::set_concatenate_results(configs.get_concatenation() == Concatenation::Any);
+    schemes_lib::set_canonical_trees(configs.get_trees() == Trees::Canonical);
+    schemes_lib::set_decimal_forms(
+        configs.get_decimals() != Decimals::Off,
+        configs.get_decimals() == Decimals::Repeating,
+    );
+    generator::set_interval_pruning(configs.get_pruning() == Pruning::Intervals);
 
-    let number = configs.get_number();
+    if let Some(output) = configs.get_output() {
+        if let Err(err) = custom_writer::create_file_to_write(output, configs.is_sorted()) {
+            eprintln!("error: cannot create \"{}\": {}", output, err);
+            process::exit(1);
+        }
+    }
+
+    let format = configs.get_format();
     let bin_operations = configs.get_operations();
-    let operands = configs_lib::Config::generate_operands(configs_lib::Operands::_DigitsAscending);
+    let operands = configs.get_operands();
+    let search_strategy = strategies::create(configs.get_strategy(), &configs);
+    let strategy = search_strategy.name();
+    let start = Instant::now();
+    report::print_header(format);
+
+    match configs.get_mode() {
+        Mode::Solve { number, explain } => {
+            let outcome = if ruled_out(&configs, &operands, *number, format) {
+                strategies::SearchOutcome::default()
+            } else {
+                search_strategy.search(&operands, &bin_operations, *number)
+            };
+            print_solution(
+                format, *number, &operands, outcome, *explain, start, strategy,
+            );
+        }
+        Mode::Coordinate {
+            number,
+            listen,
+            shard_size,
+        } => {
+            let verbose = format == Format::Text;
+            let outcome = if ruled_out(&configs, &operands, *number, format) {
+                Ok(strategies::SearchOutcome::default())
+            } else {
+                distributed::coordinate(&configs, *number, listen, *shard_size, verbose)
+            };
+            match outcome {
+                Ok(outcome) => {
+                    print_solution(format, *number, &operands, outcome, false, start, strategy)
+                }
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    process::exit(1);
+                }
+            }
+        }
+        Mode::Work { connect } => {
+            if let Err(err) = distributed::work(connect, format == Format::Text) {
+                eprintln!("error: {}", err);
+                process::exit(1);
+            }
+        }
+        Mode::Table { first, last } => {
+            // the table of the original puzzle: every number over the operands and over the
+            // reversed operands, the two answers side by side
+            let reversed_operands: Vec<String> = operands.iter().rev().cloned().collect();
+            let analyses = [
+                residue_analysis(&configs, &operands),
+                residue_analysis(&configs, &reversed_operands),
+            ];
+            let (mut total_evaluated, mut total_pruned) = (0, 0);
+            let (mut total_skipped, mut total_cut) = (0, 0);
 
-    // let schemes = schemes_lib::generate_schemes(operands);
-    // generator::generate_threads(schemes, bin_operations, number);
+            if format == Format::Text {
+                println!("number\tascending\tdescending");
+            }
+            for number in (*first..=*last).map(Rational::from) {
+                let mut row = Vec::<String>::new();
+                for (row_operands, analysis) in
+                    [&operands, &reversed_operands].iter().zip(&analyses)
+                {
+                    if let Some(analysis) = analysis {
+                        if analysis.check(&number).is_some() {
+                            row.push(String::from("-"));
+                            continue;
+                        }
+                    }
+                    let search_start = Instant::now();
+                    let outcome = search_strategy.search(row_operands, &bin_operations, number);
+                    let (evaluated, pruned) = (outcome.evaluated, outcome.pruned);
+                    total_evaluated += evaluated;
+                    total_pruned += pruned;
+                    total_skipped += outcome.skipped;
+                    total_cut += outcome.cut;
 
-    let schemes = schemes_lib::generate_schemes(operands);
-    generator::generate_threads(schemes, bin_operations, number);
+                    match outcome.result {
+                        Some(result) => {
+                            write_result(number, &result);
+                            report::print_record(
+                                format,
+                                &Record::solution(number, row_operands, &result)
+                                    .with_stats(
+                                        search_start.elapsed().as_secs_f64(),
+                                        evaluated,
+                                        pruned,
+                                        strategy,
+                                    )
+                                    .with_cuts(outcome.skipped, outcome.cut),
+                            );
+                            row.push(infix(&result));
+                        }
+                        None => row.push(String::from("-")),
+                    }
+                }
+                if format == Format::Text {
+                    println!("{}\t{}\t{}", number, row[0], row[1]);
+                }
+            }
 
-    // generator::generate_threads2(operands, bin_operations, number);
+            if format == Format::Text {
+                println!("Total time: {}s", start.elapsed().as_secs());
+            }
+            report::print_record(
+                format,
+                &Record::summary(None, &operands)
+                    .with_stats(
+                        start.elapsed().as_secs_f64(),
+                        total_evaluated,
+                        total_pruned,
+                        strategy,
+                    )
+                    .with_cuts(total_skipped, total_cut),
+            );
+        }
+        Mode::Verify { expression, number } => {
+            let calculated = match rpn_evaluator::evaluate(expression) {
+                Ok(calculated) => calculated,
+                Err(err) => {
+                    eprintln!("error: {}", err);
+                    process::exit(1);
+                }
+            };
+            if format == Format::Text {
+                println!("{}", infix(expression));
+                println!("= {}", calculated);
+            }
+            let mut record =
+                Record::solution(Rational::ZERO, &expression_operands(expression), expression);
+            record.target = *number;
+            report::print_record(format, &record);
 
+            if let Some(number) = number {
+                if !number.matches(expression, calculated) {
+                    eprintln!("error: expression is not equal to {}", number);
+                    process::exit(1);
+                }
+            }
+        }
+        Mode::Explain { expression } => {
+            if format == Format::Text {
+                print_explanation(expression);
+            } else if let Err(err) = rpn_evaluator::explain(expression) {
+                eprintln!("error: {}", err);
+                process::exit(1);
+            } else {
+                let operands = expression_operands(expression);
+                report::print_record(
+                    format,
+                    &Record::solution(Rational::ZERO, &operands, expression),
+                );
+            }
+        }
+        Mode::Benchmark {
+            targets,
+            operand_sets,
+            strategies,
+            repeats,
+        } => {
+            benchmark::run(&configs, targets, operand_sets, strategies, *repeats);
+            if format == Format::Text {
+                println!("Total time: {}s", start.elapsed().as_secs());
+            }
+        }
+        Mode::Schemes { index, expression } => print_schemes(&operands, *index, expression),
+        Mode::Residues { modulus, residue } => {
+            let schemes = strategies::generate_schemes(
+                operands.clone(),
+                configs.get_permutations(),
+                configs.get_scheme_range(),
+            );
+            let found = generator::search_residues(schemes, bin_operations, *modulus, *residue);
+            let (evaluated, pruned) = generator::get_search_stats();
+            let skipped = schemes_lib::get_skipped();
 
-    // thread::sleep(Duration::from_secs(5));
+            if format == Format::Text {
+                print_residues(&found, *modulus, *residue);
+                println!("Total time: {}s", start.elapsed().as_secs());
+                println!("Skipped equivalent trees: {}", skipped);
+            }
+            for (residue, result) in &found {
+                report::print_record(format, &Record::residue(*residue, &operands, result));
+            }
+            report::print_record(
+                format,
+                &Record::summary(residue.map(Rational::from), &operands)
+                    .with_stats(start.elapsed().as_secs_f64(), evaluated, pruned, strategy)
+                    .with_cuts(skipped, 0),
+            );
+        }
+        Mode::Enumerate => {
+            let schemes = strategies::generate_schemes(
+                operands.clone(),
+                configs.get_permutations(),
+                configs.get_scheme_range(),
+            );
+            generator::enumerate_expressions(schemes, bin_operations);
+            let (evaluated, pruned) = generator::get_search_stats();
+            let skipped = schemes_lib::get_skipped();
+            if format == Format::Text {
+                println!(
+                    "Finish enumeration\nTotal time: {}s",
+                    start.elapsed().as_secs()
+                );
+                println!("Skipped equivalent trees: {}", skipped);
+            }
+            report::print_record(
+                format,
+                &Record::summary(None, &operands)
+                    .with_stats(start.elapsed().as_secs_f64(), evaluated, pruned, strategy)
+                    .with_cuts(skipped, 0),
+            );
+        }
+    }
+
+    if let Err(err) = custom_writer::finish_writing() {
+        eprintln!("error: {}", err);
+        process::exit(1);
+    }
 
     // println!("{}", rpn_evaluator::evaluate("1 2 3 4 5 6 7 _ 8 + / / + 9 / + +").unwrap());
 }
+
+// Residue analysis of the operands in their order, `None` when it cannot be made.
+fn residue_analysis(configs: &Config, operands: &[String]) -> Option<ResidueAnalysis> {
+    // decimal forms of the operands have no residues
+    if configs.get_permutations() != Permutations::Off || configs.get_decimals() != Decimals::Off {
+        return None;
+    }
+    ResidueAnalysis::new(
+        operands,
+        &configs.get_operations(),
+        configs.get_concatenation() == Concatenation::Any,
+    )
+}
+
+// Whether the residues rule the number out before any search, printing why.
+fn ruled_out(configs: &Config, operands: &[String], number: Rational, format: Format) -> bool {
+    let reason = residue_analysis(configs, operands).and_then(|x| x.check(&number));
+    if let (Some(reason), Format::Text) = (&reason, format) {
+        println!("Ruled out without a search: {}", reason);
+    }
+    reason.is_some()
+}
+
+// The expression found for the residue, or every residue reached with its first expression.
+// Expressions whose real value is out of the evaluator bounds are printed in RPN.
+fn print_residues(found: &[(u64, String)], modulus: u64, residue: Option<u64>) {
+    let infix = |result: &String| rpn_evaluator::to_infix(result).unwrap_or(result.clone());
+    match (residue, found.first()) {
+        (Some(_), Some((residue, result))) => println!(
+            "\n{}\n{} = {} (mod {})\n",
+            result,
+            infix(result),
+            residue,
+            modulus
+        ),
+        (Some(residue), None) => println!("No expression is {} mod {}", residue, modulus),
+        (None, _) => {
+            println!("residue\texpression");
+            for (residue, result) in found {
+                println!("{}\t{}", residue, infix(result));
+            }
+            println!("{} of {} residues reachable", found.len(), modulus);
+        }
+    }
+}
+
+fn print_solution(
+    format: Format,
+    number: Rational,
+    operands: &[String],
+    outcome: strategies::SearchOutcome,
+    explain: bool,
+    start: Instant,
+    strategy: &str,
+) {
+    let (result, evaluated, pruned) = (outcome.result, outcome.evaluated, outcome.pruned);
+    let (skipped, cut) = (outcome.skipped, outcome.cut);
+    let elapsed = start.elapsed().as_secs_f64();
+
+    if format == Format::Text {
+        match &result {
+            Some(result) => {
+                println!("\n{}\n{}\n", result, infix(result));
+                if explain {
+                    print_explanation(result);
+                    println!();
+                }
+                println!("Finish\nTotal Time: {}s", start.elapsed().as_secs());
+            }
+            None => println!(
+                "Number {} is UNREACHABLE\nTotal time: {}s",
+                number,
+                start.elapsed().as_secs()
+            ),
+        }
+        println!("Skipped equivalent trees: {}", skipped);
+        println!("Branches cut by intervals: {}", cut);
+    }
+    if let Some(result) = &result {
+        write_result(number, result);
+        report::print_record(
+            format,
+            &Record::solution(number, operands, result)
+                .with_stats(elapsed, evaluated, pruned, strategy)
+                .with_cuts(skipped, cut),
+        );
+    }
+    report::print_record(
+        format,
+        &Record::summary(Some(number), operands)
+            .with_stats(elapsed, evaluated, pruned, strategy)
+            .with_cuts(skipped, cut),
+    );
+}
+
+fn infix(result: &str) -> String {
+    match rpn_evaluator::to_infix(result) {
+        Ok(x) => x,
+        Err(err) => err,
+    }
+}
+
+fn expression_operands(expression: &str) -> Vec<String> {
+    expression
+        .split_whitespace()
+        .filter(|x| rpn_evaluator::parse_operand(x).is_some())
+        .map(|x| x.to_string())
+        .collect()
+}
+
+// Prints the stack operations of the expression one per line, exits on an evaluation error.
+fn print_explanation(expression: &str) {
+    let steps = match rpn_evaluator::explain(expression) {
+        Ok(steps) => steps,
+        Err(err) => {
+            eprintln!("error: {}", err);
+            process::exit(1);
+        }
+    };
+    let stacks: Vec<String> = steps
+        .iter()
+        .map(|step| {
+            step.stack
+                .iter()
+                .map(|value| value.to_string())
+                .collect::<Vec<String>>()
+                .join(" ")
+        })
+        .collect();
+    let operation_width = steps
+        .iter()
+        .map(|step| step.operation.len())
+        .max()
+        .unwrap_or(0)
+        .max(9);
+    let stack_width = stacks
+        .iter()
+        .map(|stack| stack.len())
+        .max()
+        .unwrap_or(0)
+        .max(5);
+
+    println!(
+        "{:>4}  {:<ow$}  {:<sw$}  sub-expression",
+        "step",
+        "operation",
+        "stack",
+        ow = operation_width,
+        sw = stack_width
+    );
+    for (i, (step, stack)) in steps.iter().zip(stacks).enumerate() {
+        println!(
+            "{:>4}  {:<ow$}  {:<sw$}  {}",
+            i + 1,
+            step.operation,
+            stack,
+            step.infix,
+            ow = operation_width,
+            sw = stack_width
+        );
+    }
+}
+
+// Number of schemes, the scheme at `index` or the index of the scheme of `expression`.
+fn print_schemes(operands: &[String], index: Option<u128>, expression: &Option<String>) {
+    if let Some(expression) = expression {
+        let count = expression_operands(expression).len();
+        match schemes_lib::rank_expression(expression) {
+            Some(rank) => println!(
+                "scheme {} of {}",
+                rank,
+                schemes_lib::count_schemes(count.saturating_sub(1))
+            ),
+            None => {
+                eprintln!("error: \"{}\" is not a complete RPN expression", expression);
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let count = schemes_lib::count_schemes(operands.len().saturating_sub(1));
+    match index {
+        Some(index) => match schemes_lib::unrank_schema(operands.to_vec(), index) {
+            Some(schema) => println!("{}", schema.get_pattern("?")),
+            None => {
+                eprintln!(
+                    "error: index {} is out of range, there are {} schemes",
+                    index, count
+                );
+                process::exit(1);
+            }
+        },
+        None => println!("{} schemes over {} operands", count, operands.len()),
+    }
+}
+
+fn write_result(number: Rational, result: &str) {
+    if let Err(err) = custom_writer::write_to_file(&number, result) {
+        eprintln!("error: {}", err);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn operands_of_expressions() {
+        assert_eq!(expression_operands("1 2 _ 3 +"), vec!["1", "2", "3"]);
+        assert_eq!(
+            expression_operands(".(3) 1.2(3) + .5 * 4 /"),
+            vec![".(3)", "1.2(3)", ".5", "4"]
+        );
+        assert_eq!(expression_operands("1 inf + x %"), vec!["1"]);
+    }
+}
@@ -0,0 +1,159 @@
+use serde::Serialize;
+
+use crazy_representation_core::rational::Rational;
+
+use crate::configs_lib::Format;
+
+const CSV_HEADER: &str =
+    "record,target,operands,rpn,infix,value,elapsed,evaluated,pruned,strategy,dedup_peak,skipped,cut";
+
+// One line of machine-readable output. A "solution" record is printed for every expression
+// found, a "summary" record once at the end of the run. The benchmark prints a "benchmark"
+// record per strategy, operands and target with the mean of its runs, the modular search
+// a "residue" record per residue found with the residue as the target.
+#[derive(Debug, Serialize)]
+pub struct Record {
+    pub record: &'static str,
+    pub target: Option<Rational>,
+    pub operands: String,
+    pub rpn: Option<String>,
+    pub infix: Option<String>,
+    pub value: Option<f64>,
+    pub elapsed: f64,
+    pub evaluated: u64,
+    pub pruned: u64,
+    pub strategy: String,
+    pub dedup_peak: Option<usize>,
+    pub skipped: u64, // operators not placed as their tree is not canonical
+    pub cut: u64,     // branches cut as their interval of values misses the target
+}
+
+impl Record {
+    pub fn solution(target: Rational, operands: &[String], rpn: &str) -> Record {
+        Record {
+            record: "solution",
+            target: Some(target),
+            operands: operands.join(" "),
+            rpn: Some(rpn.trim().to_string()),
+            infix: crate::rpn_evaluator::to_infix(rpn).ok(),
+            value: crate::rpn_evaluator::evaluate(rpn).ok(),
+            elapsed: 0.0,
+            evaluated: 0,
+            pruned: 0,
+            strategy: String::new(),
+            dedup_peak: None,
+            skipped: 0,
+            cut: 0,
+        }
+    }
+
+    pub fn summary(target: Option<Rational>, operands: &[String]) -> Record {
+        Record {
+            record: "summary",
+            target,
+            operands: operands.join(" "),
+            rpn: None,
+            infix: None,
+            value: None,
+            elapsed: 0.0,
+            evaluated: 0,
+            pruned: 0,
+            strategy: String::new(),
+            dedup_peak: None,
+            skipped: 0,
+            cut: 0,
+        }
+    }
+
+    pub fn benchmark(target: Rational, operands: &[String], rpn: Option<&str>) -> Record {
+        let mut record = match rpn {
+            Some(rpn) => Record::solution(target, operands, rpn),
+            None => Record::summary(Some(target), operands),
+        };
+        record.record = "benchmark";
+        record
+    }
+
+    pub fn residue(residue: u64, operands: &[String], rpn: &str) -> Record {
+        let mut record = Record::solution(Rational::from(residue), operands, rpn);
+        record.record = "residue";
+        record
+    }
+
+    pub fn with_stats(
+        mut self,
+        elapsed: f64,
+        evaluated: u64,
+        pruned: u64,
+        strategy: &str,
+    ) -> Record {
+        self.elapsed = elapsed;
+        self.evaluated = evaluated;
+        self.pruned = pruned;
+        self.strategy = strategy.to_string();
+        self
+    }
+
+    pub fn with_cuts(mut self, skipped: u64, cut: u64) -> Record {
+        self.skipped = skipped;
+        self.cut = cut;
+        self
+    }
+}
+
+// Prints the CSV header, other formats have nothing to print before the first record.
+pub fn print_header(format: Format) {
+    if format == Format::Csv {
+        println!("{}", CSV_HEADER);
+    }
+}
+
+// Prints the record for the machine-readable formats, does nothing for `Format::Text`.
+pub fn print_record(format: Format, record: &Record) {
+    match format {
+        Format::Text => {}
+        Format::Jsonl => match serde_json::to_string(record) {
+            Ok(line) => println!("{}", line),
+            Err(err) => eprintln!("error: {}", err),
+        },
+        Format::Csv => println!("{}", csv_line(record)),
+    }
+}
+
+fn csv_line(record: &Record) -> String {
+    let fields = [
+        record.record.to_string(),
+        optional_field(&record.target),
+        record.operands.clone(),
+        optional_field(&record.rpn),
+        optional_field(&record.infix),
+        optional_field(&record.value),
+        record.elapsed.to_string(),
+        record.evaluated.to_string(),
+        record.pruned.to_string(),
+        record.strategy.clone(),
+        optional_field(&record.dedup_peak),
+        record.skipped.to_string(),
+        record.cut.to_string(),
+    ];
+    fields
+        .iter()
+        .map(|field| csv_field(field))
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+fn optional_field<T: ToString>(value: &Option<T>) -> String {
+    match value {
+        Some(x) => x.to_string(),
+        None => String::new(),
+    }
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
@@ -1,11 +1,47 @@
 use std::cmp::min;
 
+use crazy_representation_core::operators::Operator;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+// whether `_` may also join results of subexpressions, not only adjacent operands
+static CONCATENATE_RESULTS: AtomicBool = AtomicBool::new(false);
+// whether trees equal to another one by associativity are skipped, see `Operator::is_canonical_with`
+static CANONICAL_TREES: AtomicBool = AtomicBool::new(true);
+static SKIPPED: AtomicU64 = AtomicU64::new(0); // operators not inserted as not canonical
+// whether every operand is also written as .5 and as .(5), see `decimal_forms`
+static DECIMAL_POINT: AtomicBool = AtomicBool::new(false);
+static REPEATING_DECIMALS: AtomicBool = AtomicBool::new(false);
+
+pub fn set_concatenate_results(concatenate_results: bool) {
+    CONCATENATE_RESULTS.store(concatenate_results, Ordering::Relaxed);
+}
+
+pub fn set_canonical_trees(canonical_trees: bool) {
+    CANONICAL_TREES.store(canonical_trees, Ordering::Relaxed);
+}
+
+pub fn set_decimal_forms(decimal_point: bool, repeating_decimals: bool) {
+    DECIMAL_POINT.store(decimal_point, Ordering::Relaxed);
+    REPEATING_DECIMALS.store(repeating_decimals, Ordering::Relaxed);
+}
+
+// Number of operators not inserted since the last reset as their tree is not canonical,
+// every one of them skips the subtrees below.
+pub fn get_skipped() -> u64 {
+    SKIPPED.load(Ordering::Relaxed)
+}
+
+pub fn reset_skipped() {
+    SKIPPED.store(0, Ordering::SeqCst);
+}
+
 #[derive(Debug, Clone)]
 pub struct Schema {
     pub result_vec: Vec<String>,
     pub positions: Vec<i32>,
     pub position_pointer: i16,
     pub prev_not_concat_op_pos: i16,
+    pub may_round: bool, // a division or power is inserted, results may be rounded
 }
 
 impl Schema {
@@ -14,7 +50,8 @@ impl Schema {
             result_vec: Vec::<String>::new(),
             positions: Vec::<i32>::new(),
             position_pointer: -1,
-            prev_not_concat_op_pos: -1
+            prev_not_concat_op_pos: -1,
+            may_round: false,
         }
     }
 
@@ -23,7 +60,8 @@ impl Schema {
             result_vec: operands.clone(),
             positions: Vec::<i32>::new(),
             position_pointer: -1,
-            prev_not_concat_op_pos: -1
+            prev_not_concat_op_pos: -1,
+            may_round: false,
         }
     }
 
@@ -32,7 +70,8 @@ impl Schema {
             result_vec: Vec::<String>::new(),
             positions: positions.clone(),
             position_pointer: positions.len() as i16 - 1,
-            prev_not_concat_op_pos: -1
+            prev_not_concat_op_pos: -1,
+            may_round: false,
         }
     }
 
@@ -42,6 +81,7 @@ impl Schema {
             positions: schema.positions.clone(),
             position_pointer: schema.position_pointer,
             prev_not_concat_op_pos: schema.prev_not_concat_op_pos,
+            may_round: schema.may_round,
         }
     }
 
@@ -53,11 +93,22 @@ impl Schema {
         let mut answer = String::from("");
         for symbol in self.result_vec.iter() {
             answer.push_str(symbol);
-            answer.push_str(" ")
+            answer.push(' ')
         }
         answer
     }
 
+    // Operands of a fresh schema with `placeholder` at every operator position.
+    pub fn get_pattern(&self, placeholder: &str) -> String {
+        let mut tokens = self.result_vec.clone();
+        let mut positions = self.positions.clone();
+        positions.sort_unstable();
+        for position in positions {
+            tokens.insert(position as usize, placeholder.to_string());
+        }
+        tokens.join(" ")
+    }
+
     fn _add_position(&mut self, position: i32) {
         self.positions.push(position);
         self.position_pointer += 1;
@@ -66,25 +117,29 @@ impl Schema {
     pub fn insert_operator(&mut self, operator: String) -> bool {
         if Schema::check_conditions(self, &operator) {
             // if true {
-            return match self.positions.get(self.position_pointer as usize) {
+            match self.positions.get(self.position_pointer as usize) {
                 None => false,
                 Some(idx) => {
-                    if operator != "_"{
+                    let definition = Operator::from_symbol(&operator);
+                    if !definition.is_some_and(|x| x.joins_digits()) {
                         self.prev_not_concat_op_pos = *idx as i16;
                     }else{
                         self.prev_not_concat_op_pos += 2;
                     }
+                    if definition.is_some_and(|x| x.may_round()) {
+                        self.may_round = true;
+                    }
                     self.result_vec.insert(*idx as usize, operator);
                     self.position_pointer -= 1;
                     true
                 }
-            };
+            }
         } else {
             false
         }
     }
 
-    fn check_conditions(schema: &mut Schema, operator: &String) -> bool {
+    fn check_conditions(schema: &mut Schema, operator: &str) -> bool {
         let current_position = match schema.positions.get(schema.position_pointer as usize) {
             None => {
                 return false;
@@ -97,7 +152,23 @@ impl Schema {
         //     Some(position) => *position,
         // };
 
-        if operator == "_"
+        // the right operand of the operator ends right before it; the trees are only equal
+        // while every value is an integer, as the rounding of a fraction depends on the tree
+        if CANONICAL_TREES.load(Ordering::Relaxed) && !schema.may_round && current_position > 0 {
+            let right = schema.result_vec.get(current_position as usize - 1);
+            if let (Some(operator), Some(right)) = (
+                Operator::from_symbol(operator),
+                right.and_then(|x| Operator::from_symbol(x)),
+            ) {
+                if !operator.is_canonical_with(&right) {
+                    SKIPPED.fetch_add(1, Ordering::Relaxed);
+                    return false;
+                }
+            }
+        }
+
+        if Operator::from_symbol(operator).is_some_and(|x| x.joins_digits())
+            && !CONCATENATE_RESULTS.load(Ordering::Relaxed)
             // && !((schema.prev_op == "" && current_position >= 2) || schema.prev_op == "_")
             && (current_position - schema.prev_not_concat_op_pos as i32) <= 2
         {
@@ -133,57 +204,623 @@ impl Schema {
     }
 }
 
-pub fn generate_schemes(operands: Vec<String>) -> Vec<Schema> {
-    let mut results = Vec::<Schema>::new();
-    produce_schema(
-        &mut results,
-        (operands.len() - 1) as i32,
-        (operands.len() - 1) as i32,
-        0,
-        Vec::<i32>::new(),
+// Schemes produced one by one, so the search can start before all of them are known.
+// With decimal forms set, each scheme is followed by the other forms of its operands.
+pub struct Schemes {
+    pub max_operators: usize,
+    pub forms: usize, // most forms of the operands of a scheme, see `count_forms`
+    decimal_point: bool,
+    repeating_decimals: bool,
+    iter: Box<dyn Iterator<Item = Schema> + Send>,
+    pending: std::vec::IntoIter<Schema>,
+}
+
+impl Schemes {
+    fn new(max_operators: usize, iter: Box<dyn Iterator<Item = Schema> + Send>) -> Schemes {
+        let decimal_point = DECIMAL_POINT.load(Ordering::Relaxed);
+        let repeating_decimals = REPEATING_DECIMALS.load(Ordering::Relaxed);
+        Schemes {
+            max_operators,
+            forms: count_forms(max_operators + 1, decimal_point, repeating_decimals),
+            decimal_point,
+            repeating_decimals,
+            iter,
+            pending: Vec::new().into_iter(),
+        }
+    }
+}
+
+impl Iterator for Schemes {
+    type Item = Schema;
+
+    fn next(&mut self) -> Option<Schema> {
+        if !self.decimal_point && !self.repeating_decimals {
+            return self.iter.next();
+        }
+        loop {
+            if let Some(schema) = self.pending.next() {
+                return Some(schema);
+            }
+            let schema = self.iter.next()?;
+            self.pending =
+                decimal_forms(&schema, self.decimal_point, self.repeating_decimals).into_iter();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        let pending = self.pending.len();
+        (
+            lower.saturating_add(pending),
+            upper
+                .and_then(|x| x.checked_mul(self.forms))
+                .and_then(|x| x.checked_add(pending)),
+        )
+    }
+}
+
+pub fn generate_schemes(operands: Vec<String>) -> Schemes {
+    Schemes::new(
+        operands.len().saturating_sub(1),
+        Box::new(SchemeIter::new(operands)),
+    )
+}
+
+// Every form of an operand: as it is given and, when they are set, after a decimal point
+// and as the repeating part of a decimal. .0 and .(0) are 0 again, so they are left out.
+fn operand_forms(operand: &str, decimal_point: bool, repeating_decimals: bool) -> Vec<String> {
+    let mut forms = vec![operand.to_string()];
+    if operand.chars().all(|c| c == '0') {
+        return forms;
+    }
+    if decimal_point {
+        forms.push(format!(".{}", operand));
+    }
+    if repeating_decimals {
+        forms.push(format!(".({})", operand));
+    }
+    forms
+}
+
+// Most forms of a scheme over `operands` operands: every operand has up to three forms and
+// every run of two or more operands up to two, as a run is also the concatenation itself.
+fn count_forms(operands: usize, decimal_point: bool, repeating_decimals: bool) -> usize {
+    let decimals = decimal_point as usize + repeating_decimals as usize;
+    let mut counts = vec![1usize];
+    for count in 1..=operands {
+        let runs = counts[..count.saturating_sub(1)]
+            .iter()
+            .fold(0usize, |sum, x| sum.saturating_add(*x));
+        counts.push(
+            counts[count - 1]
+                .saturating_mul(decimals + 1)
+                .saturating_add(runs.saturating_mul(decimals)),
+        );
+    }
+    counts[operands]
+}
+
+// Each form of a scheme, the operands as they are given first. Every operand has its own
+// forms, and a run of operands concatenated right after each other, 1 2 _ 3 _, also the
+// forms of its digits .123 and .(123): the run becomes a single operand in place of its
+// concatenations. The forms are alternatives of the operands, so the scheme keeps its index.
+fn decimal_forms(schema: &Schema, decimal_point: bool, repeating_decimals: bool) -> Vec<Schema> {
+    // the tokens of the scheme, None at the operator positions
+    let mut tokens: Vec<Option<&str>> =
+        schema.result_vec.iter().map(|x| Some(x.as_str())).collect();
+    let mut positions = schema.positions.clone();
+    positions.sort_unstable();
+    for position in positions {
+        tokens.insert(position as usize, None);
+    }
+    let mut forms = Vec::new();
+    push_forms(
+        &tokens,
+        (decimal_point, repeating_decimals),
+        &mut Vec::new(),
+        &mut forms,
     );
-    for result in results.iter_mut() {
-        result.set_result_vec(operands.clone());
+    forms
+        .into_iter()
+        .map(|form| {
+            let positions: Vec<i32> = (0..form.len() as i32)
+                .rev()
+                .filter(|x| form[*x as usize].is_none())
+                .collect();
+            let mut copy_schema = Schema::from_positions(positions);
+            copy_schema.set_result_vec(form.into_iter().flatten().collect());
+            copy_schema
+        })
+        .collect()
+}
+
+fn push_forms(
+    tokens: &[Option<&str>],
+    decimals: (bool, bool),
+    form: &mut Vec<Option<String>>,
+    forms: &mut Vec<Vec<Option<String>>>,
+) {
+    let mut push = |token: Option<String>, rest: &[Option<&str>], forms: &mut Vec<_>| {
+        form.push(token);
+        push_forms(rest, decimals, form, forms);
+        form.pop();
+    };
+    let operand = match tokens.first() {
+        None => return forms.push(form.clone()),
+        Some(None) => return push(None, &tokens[1..], forms),
+        Some(Some(operand)) => operand,
+    };
+    for operand_form in operand_forms(operand, decimals.0, decimals.1) {
+        push(Some(operand_form), &tokens[1..], forms);
+    }
+    // a run is an operand, the next one and an operator after each further operand
+    let mut digits = operand.to_string();
+    let mut end = 1;
+    while let (Some(Some(next)), Some(None)) = (tokens.get(end), tokens.get(end + 1)) {
+        digits.push_str(next);
+        end += 2;
+        for run_form in operand_forms(&digits, decimals.0, decimals.1)
+            .into_iter()
+            .skip(1)
+        {
+            push(Some(run_form), &tokens[end..], forms);
+        }
+    }
+}
+
+// Schemes for every distinct order of the operands ("four fours", 24 game, Countdown).
+// Orders that only swap equal operands are generated once. With `use_subsets` every
+// non-empty subset of the operands is arranged as well.
+pub fn generate_permuted_schemes(operands: Vec<String>, use_subsets: bool) -> Schemes {
+    let mut sorted_operands = operands;
+    sorted_operands.sort();
+
+    Schemes::new(
+        sorted_operands.len().saturating_sub(1),
+        Box::new(Arrangements::new(sorted_operands, use_subsets).flat_map(SchemeIter::new)),
+    )
+}
+
+// Schemes with indices in `start..end` of the order of `generate_schemes`,
+// the first one is found without producing the schemes before it.
+pub fn generate_schemes_range(operands: Vec<String>, start: u128, end: u128) -> Schemes {
+    let count = end.saturating_sub(start);
+    Schemes::new(
+        operands.len().saturating_sub(1),
+        Box::new(
+            SchemeIter::from_index(operands, start).take(min(count, usize::MAX as u128) as usize),
+        ),
+    )
+}
+
+// Number of schemes over `operators + 1` operands in a fixed order, the Catalan number.
+pub fn count_schemes(operators: usize) -> u128 {
+    let mut count: u128 = 1;
+    for k in 0..operators as u128 {
+        count = count.saturating_mul(2 * (2 * k + 1)) / (k + 2);
+    }
+    count
+}
+
+// A scheme is the sequence of its tokens: an operand is pushed while there are operands left,
+// an operator can be placed while at least two values are on the stack. `table[p][u]` is the
+// number of ways to finish a scheme with `p` operands pushed and `u` operators placed.
+fn completions_table(operands: usize) -> Vec<Vec<u128>> {
+    let operators = operands.saturating_sub(1);
+    let mut table = vec![vec![0u128; operators + 1]; operands + 1];
+    for pushed in (0..=operands).rev() {
+        for placed in (0..=operators).rev() {
+            table[pushed][placed] = if pushed == operands && placed == operators {
+                1
+            } else {
+                let mut count: u128 = 0;
+                if pushed < operands {
+                    count = count.saturating_add(table[pushed + 1][placed]);
+                }
+                if placed < operators && pushed > placed + 1 {
+                    count = count.saturating_add(table[pushed][placed + 1]);
+                }
+                count
+            };
+        }
+    }
+    table
+}
+
+// Index in the order of `generate_schemes` of the scheme with operators at the given
+// positions of the expression, `None` when the positions do not make a scheme.
+pub fn rank_positions(operands: usize, positions: &[i32]) -> Option<u128> {
+    let operators = operands.checked_sub(1)?;
+    if positions.len() != operators {
+        return None;
+    }
+    let table = completions_table(operands);
+    let (mut pushed, mut placed, mut rank) = (0, 0, 0u128);
+    for token in 0..(operands + operators) as i32 {
+        if positions.contains(&token) {
+            if placed >= operators || pushed <= placed + 1 {
+                return None;
+            }
+            // every scheme pushing an operand here comes first
+            if pushed < operands {
+                rank += table[pushed + 1][placed];
+            }
+            placed += 1;
+        } else {
+            if pushed >= operands {
+                return None;
+            }
+            pushed += 1;
+        }
     }
-    results
+    Some(rank)
 }
 
-fn produce_schema(
-    results: &mut Vec<Schema>,
-    available: i32,
+#[cfg(test)]
+fn rank_schema(schema: &Schema) -> Option<u128> {
+    rank_positions(schema.positions.len() + 1, &schema.positions)
+}
+
+// Index of the scheme of an RPN expression.
+pub fn rank_expression(expression: &str) -> Option<u128> {
+    let tokens: Vec<&str> = expression.split_whitespace().collect();
+    let positions: Vec<i32> = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| Operator::from_symbol(token).is_some())
+        .map(|(idx, _)| idx as i32)
+        .collect();
+    rank_positions(tokens.len() - positions.len(), &positions)
+}
+
+pub fn unrank_schema(operands: Vec<String>, index: u128) -> Option<Schema> {
+    SchemeIter::from_index(operands, index).next()
+}
+
+// Depth-first walk over the operator positions, the same order as the recursive
+// `produce_schema` it replaces: the branch pushed last on the stack is explored first.
+struct SchemeIter {
+    operands: Vec<String>,
     max_ops: i32,
-    step: i32,
-    positions: Vec<i32>,
-) {
-    if available == 0 && step == (max_ops + 1) {
-        results.push(Schema::from_positions(
-            positions.into_iter().rev().collect(),
-        ));
-        return;
+    stack: Vec<(i32, i32, Vec<i32>)>, // (available operators, step, positions)
+}
+
+impl SchemeIter {
+    // Iterator positioned at the scheme with the given index: the stack holds the branches
+    // the walk from the first scheme would still have to explore.
+    fn from_index(operands: Vec<String>, index: u128) -> SchemeIter {
+        let count = operands.len();
+        let mut iter = SchemeIter::new(operands);
+        if count == 0 || index >= count_schemes(count - 1) {
+            iter.stack.clear();
+            return iter;
+        }
+        let max_ops = iter.max_ops;
+        let table = completions_table(count);
+        let (mut pushed, mut placed, mut index) = (0, 0, index);
+        let mut positions = Vec::<i32>::new();
+        iter.stack.clear();
+        while pushed < count || placed < count - 1 {
+            let can_place = placed < count - 1 && pushed > placed + 1;
+            let with_push = if pushed < count {
+                table[pushed + 1][placed]
+            } else {
+                0
+            };
+            if index < with_push {
+                if can_place {
+                    let mut branch = positions.clone();
+                    branch.push((pushed + placed) as i32);
+                    iter.stack
+                        .push((max_ops - placed as i32 - 1, pushed as i32, branch));
+                }
+                pushed += 1;
+            } else {
+                index -= with_push;
+                positions.push((pushed + placed) as i32);
+                placed += 1;
+            }
+        }
+        iter.stack.push((0, max_ops + 1, positions));
+        iter
     }
 
-    if step < max_ops + 1 {
-        produce_schema(results, available, max_ops, step + 1, positions.clone());
+    fn new(operands: Vec<String>) -> SchemeIter {
+        let max_ops = operands.len() as i32 - 1;
+        SchemeIter {
+            operands,
+            max_ops,
+            stack: if max_ops < 0 {
+                Vec::new()
+            } else {
+                vec![(max_ops, 0, Vec::<i32>::new())]
+            },
+        }
     }
+}
+
+impl Iterator for SchemeIter {
+    type Item = Schema;
 
-    let can_use = min(available, step - 2 - (max_ops - 1 - available));
-    if can_use > 0 {
-        let mut new_positions = positions;
-        new_positions.push(step + (max_ops - available));
-        produce_schema(results, available - 1, max_ops, step, new_positions);
+    fn next(&mut self) -> Option<Schema> {
+        let max_ops = self.max_ops;
+        while let Some((available, step, positions)) = self.stack.pop() {
+            if available == 0 && step == (max_ops + 1) {
+                let mut schema = Schema::from_positions(positions.into_iter().rev().collect());
+                schema.set_result_vec(self.operands.clone());
+                return Some(schema);
+            }
+
+            let can_use = min(available, step - 2 - (max_ops - 1 - available));
+            if can_use > 0 {
+                let mut new_positions = positions.clone();
+                new_positions.push(step + (max_ops - available));
+                self.stack.push((available - 1, step, new_positions));
+            }
+            if step < max_ops + 1 {
+                self.stack.push((available, step + 1, positions));
+            }
+        }
+        None
+    }
+}
+
+// Distinct orders of the sorted operands, as indices into them.
+struct Arrangements {
+    operands: Vec<String>,
+    use_subsets: bool,
+    stack: Vec<Vec<usize>>,
+}
+
+impl Arrangements {
+    fn new(operands: Vec<String>, use_subsets: bool) -> Arrangements {
+        Arrangements {
+            operands,
+            use_subsets,
+            stack: vec![Vec::new()],
+        }
     }
 }
 
-pub fn _schemes_string(mut schemes: Vec<Schema>, op_sign: &str) -> String {
+impl Iterator for Arrangements {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Vec<String>> {
+        let count = self.operands.len();
+        while let Some(arrangement) = self.stack.pop() {
+            if arrangement.len() < count {
+                let mut used = vec![false; count];
+                for idx in arrangement.iter() {
+                    used[*idx] = true;
+                }
+                for idx in (0..count).rev() {
+                    // equal operands are taken in their sorted order only
+                    if used[idx]
+                        || (idx > 0
+                            && self.operands[idx] == self.operands[idx - 1]
+                            && !used[idx - 1])
+                    {
+                        continue;
+                    }
+                    let mut new_arrangement = arrangement.clone();
+                    new_arrangement.push(idx);
+                    self.stack.push(new_arrangement);
+                }
+            }
+            if arrangement.len() == count || (self.use_subsets && !arrangement.is_empty()) {
+                return Some(
+                    arrangement
+                        .iter()
+                        .map(|idx| self.operands[*idx].clone())
+                        .collect(),
+                );
+            }
+        }
+        None
+    }
+}
+
+// Every assignment of `length` operators, the last one changing fastest.
+pub struct OperatorSets {
+    operations: Vec<String>,
+    indices: Vec<usize>,
+    done: bool,
+}
+
+impl OperatorSets {
+    pub fn new(operations: Vec<String>, length: usize) -> OperatorSets {
+        OperatorSets {
+            done: operations.is_empty() && length > 0,
+            operations,
+            indices: vec![0; length],
+        }
+    }
+}
+
+impl Iterator for OperatorSets {
+    type Item = Vec<String>;
+
+    fn next(&mut self) -> Option<Vec<String>> {
+        if self.done {
+            return None;
+        }
+        let result = self
+            .indices
+            .iter()
+            .map(|idx| self.operations[*idx].clone())
+            .collect();
+
+        self.done = true;
+        for idx in self.indices.iter_mut().rev() {
+            *idx += 1;
+            if *idx < self.operations.len() {
+                self.done = false;
+                break;
+            }
+            *idx = 0;
+        }
+        Some(result)
+    }
+}
+
+pub fn _schemes_string(schemes: Schemes, op_sign: &str) -> String {
     let mut answer = String::new();
-    for schema in schemes.iter_mut() {
+    for mut schema in schemes {
         let ln = schema.positions.len();
         for _ in 0..ln {
             schema.insert_operator(String::from(op_sign));
         }
         answer.push_str(&schema.get_string_result());
-        answer.push_str("\n");
+        answer.push('\n');
     }
 
     answer
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn arrangements() {
+        let orders: Vec<Vec<String>> =
+            Arrangements::new(strings(&["1", "1", "2"]), false).collect();
+        assert_eq!(
+            orders,
+            vec![
+                strings(&["1", "1", "2"]),
+                strings(&["1", "2", "1"]),
+                strings(&["2", "1", "1"])
+            ]
+        );
+
+        // 1, 2, 1 1, 1 2, 2 1 and the three orders of all of them
+        let mut subsets: Vec<Vec<String>> =
+            Arrangements::new(strings(&["1", "1", "2"]), true).collect();
+        assert_eq!(subsets.len(), 8);
+        subsets.sort();
+        subsets.dedup();
+        assert_eq!(subsets.len(), 8);
+
+        assert_eq!(
+            Arrangements::new(strings(&["1", "2", "3", "4"]), false).count(),
+            24
+        );
+        assert_eq!(
+            Arrangements::new(strings(&["4", "4", "4", "4"]), false).count(),
+            1
+        );
+    }
+
+    #[test]
+    fn scheme_counts() {
+        let catalan = [1, 1, 2, 5, 14, 42, 132, 429];
+        for (operators, expected) in catalan.iter().enumerate() {
+            let operands: Vec<String> = (0..=operators).map(|x| x.to_string()).collect();
+            let mut positions: Vec<Vec<i32>> = SchemeIter::new(operands)
+                .map(|schema| schema.positions)
+                .collect();
+            assert_eq!(positions.len() as u128, *expected);
+            assert_eq!(count_schemes(operators), *expected);
+            positions.sort();
+            positions.dedup();
+            assert_eq!(positions.len() as u128, *expected);
+        }
+        assert_eq!(SchemeIter::new(Vec::new()).count(), 0);
+    }
+
+    #[test]
+    fn rank_and_unrank() {
+        for count in 1..=8 {
+            let operands: Vec<String> = (1..=count).map(|x| x.to_string()).collect();
+            for (index, schema) in SchemeIter::new(operands.clone()).enumerate() {
+                assert_eq!(rank_schema(&schema), Some(index as u128));
+                let unranked = unrank_schema(operands.clone(), index as u128).unwrap();
+                assert_eq!(unranked.positions, schema.positions);
+                assert_eq!(
+                    rank_expression(&unranked.get_pattern("+")),
+                    Some(index as u128)
+                );
+            }
+            assert!(unrank_schema(operands, count_schemes(count - 1)).is_none());
+        }
+    }
+
+    #[test]
+    fn scheme_ranges() {
+        let operands = strings(&["1", "2", "3", "4", "5", "6"]);
+        let all: Vec<Vec<i32>> = generate_schemes(operands.clone())
+            .map(|schema| schema.positions)
+            .collect();
+        assert_eq!(all.len(), 42);
+        for (start, end) in [
+            (0, 42),
+            (0, 1),
+            (5, 17),
+            (17, 42),
+            (41, 100),
+            (42, 50),
+            (9, 9),
+        ] {
+            let range: Vec<Vec<i32>> = generate_schemes_range(operands.clone(), start, end)
+                .map(|schema| schema.positions)
+                .collect();
+            let (start, end) = (start as usize, min(end as usize, all.len()));
+            assert_eq!(range, all[start.min(end)..end].to_vec());
+        }
+    }
+
+    #[test]
+    fn decimal_forms_of_runs() {
+        let patterns = |operands: &[&str], index: u128, point: bool, repeating: bool| {
+            let schema = unrank_schema(strings(operands), index).unwrap();
+            decimal_forms(&schema, point, repeating)
+                .iter()
+                .map(|x| x.get_pattern("?"))
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            patterns(&["1", "2"], 0, true, false),
+            strings(&["1 2 ?", "1 .2 ?", ".1 2 ?", ".1 .2 ?", ".12"])
+        );
+        assert_eq!(patterns(&["0", "0"], 0, true, true), strings(&["0 0 ?"]));
+        // 1 2 ? 3 ?, the run of all the operands is a single one
+        let forms = patterns(&["1", "2", "3"], 1, false, true);
+        assert_eq!(forms.len(), 8 + 2 + 1);
+        assert_eq!(
+            &forms[8..],
+            strings(&[".(12) 3 ?", ".(12) .(3) ?", ".(123)"])
+        );
+        // 1 2 3 ? ?, the 1 is not concatenated right after the 2
+        let forms = patterns(&["1", "2", "3"], 0, false, true);
+        assert_eq!(forms.len(), 8 + 2);
+        assert!(forms.contains(&"1 .(23) ?".to_string()));
+        assert!(forms.contains(&".(1) .(23) ?".to_string()));
+
+        let operands = strings(&["1", "2", "3", "4", "5"]);
+        for schema in SchemeIter::new(operands.clone()) {
+            let forms = decimal_forms(&schema, true, true);
+            assert!(forms.len() <= count_forms(operands.len(), true, true));
+            assert_eq!(forms[0].get_pattern("?"), schema.get_pattern("?"));
+        }
+        assert_eq!(count_forms(2, true, false), 5);
+        assert_eq!(count_forms(5, false, false), 1);
+    }
+
+    #[test]
+    fn operator_sets() {
+        let operations = strings(&["+", "-", "*"]);
+        let sets: Vec<Vec<String>> = OperatorSets::new(operations.clone(), 2).collect();
+        assert_eq!(sets.len(), 9);
+        assert_eq!(sets[0], strings(&["+", "+"]));
+        assert_eq!(sets[1], strings(&["+", "-"]));
+        assert_eq!(sets[8], strings(&["*", "*"]));
+        assert_eq!(OperatorSets::new(operations.clone(), 4).count(), 81);
+        assert_eq!(OperatorSets::new(operations, 0).count(), 1);
+        assert_eq!(OperatorSets::new(Vec::new(), 2).count(), 0);
+    }
+}
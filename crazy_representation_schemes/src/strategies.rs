@@ -0,0 +1,235 @@
+use crazy_representation_core::configs_lib::{
+    Concatenation, Config, Permutations, SchemeRange, Strategy, Trees,
+};
+use crazy_representation_core::rational::Rational;
+
+use crate::generator;
+use crate::schemes_lib::{self, Schemes};
+
+// Result of one search, the same for every strategy.
+#[derive(Default)]
+pub struct SearchOutcome {
+    pub result: Option<String>,
+    pub evaluated: u64,
+    pub pruned: u64,
+    pub dedup_peak: usize,
+    pub skipped: u64,
+    pub cut: u64,
+}
+
+// Every search driver takes the operands in their given order, the binary operations and
+// the target, and returns the first expression found with the search counters.
+pub trait SearchStrategy {
+    fn name(&self) -> &'static str;
+
+    fn search(
+        &self,
+        operands: &[String],
+        bin_operations: &[String],
+        number: Rational,
+    ) -> SearchOutcome;
+}
+
+pub fn create(strategy: Strategy, configs: &Config) -> Box<dyn SearchStrategy> {
+    let permutations = configs.get_permutations();
+    let scheme_range = configs.get_scheme_range();
+    match strategy {
+        Strategy::Pool => Box::new(PoolSearch {
+            permutations,
+            scheme_range,
+        }),
+        Strategy::OperationSets => Box::new(OperationSetsSearch),
+        Strategy::ThreadBundles => Box::new(ThreadBundlesSearch),
+        Strategy::OperationBundles => Box::new(OperationBundlesSearch),
+        Strategy::Snapshots => Box::new(SnapshotsSearch {
+            permutations,
+            scheme_range,
+        }),
+        Strategy::Recursive => Box::new(RecursiveSearch {
+            settings: crazy_representation::Settings {
+                split_depth: configs.get_split_depth(),
+                threads: configs.get_threads().unwrap_or_else(num_cpus::get),
+                queue_size: configs.get_queue_size(),
+                concatenate_results: configs.get_concatenation() == Concatenation::Any,
+                canonical_trees: configs.get_trees() == Trees::Canonical,
+            },
+        }),
+    }
+}
+
+pub fn generate_schemes(
+    operands: Vec<String>,
+    permutations: Permutations,
+    scheme_range: Option<SchemeRange>,
+) -> Schemes {
+    match permutations {
+        Permutations::Off => match scheme_range {
+            Some(range) => schemes_lib::generate_schemes_range(
+                operands,
+                range.start,
+                range.end.unwrap_or(u128::MAX),
+            ),
+            None => schemes_lib::generate_schemes(operands),
+        },
+        permutations => {
+            schemes_lib::generate_permuted_schemes(operands, permutations == Permutations::Subsets)
+        }
+    }
+}
+
+fn generator_outcome(result: Option<String>) -> SearchOutcome {
+    let (evaluated, pruned) = generator::get_search_stats();
+    SearchOutcome {
+        result,
+        evaluated,
+        pruned,
+        dedup_peak: generator::get_dedup_size(),
+        skipped: schemes_lib::get_skipped(),
+        cut: generator::get_cut(),
+    }
+}
+
+struct PoolSearch {
+    permutations: Permutations,
+    scheme_range: Option<SchemeRange>,
+}
+
+impl SearchStrategy for PoolSearch {
+    fn name(&self) -> &'static str {
+        Strategy::Pool.name()
+    }
+
+    fn search(
+        &self,
+        operands: &[String],
+        bin_operations: &[String],
+        number: Rational,
+    ) -> SearchOutcome {
+        let schemes = generate_schemes(operands.to_vec(), self.permutations, self.scheme_range);
+        generator_outcome(generator::search_number(
+            schemes,
+            bin_operations.to_vec(),
+            number,
+        ))
+    }
+}
+
+struct OperationSetsSearch;
+
+impl SearchStrategy for OperationSetsSearch {
+    fn name(&self) -> &'static str {
+        Strategy::OperationSets.name()
+    }
+
+    fn search(
+        &self,
+        operands: &[String],
+        bin_operations: &[String],
+        number: Rational,
+    ) -> SearchOutcome {
+        generator_outcome(generator::search_number_by_operation_sets(
+            operands.to_vec(),
+            bin_operations.to_vec(),
+            number,
+        ))
+    }
+}
+
+struct ThreadBundlesSearch;
+
+impl SearchStrategy for ThreadBundlesSearch {
+    fn name(&self) -> &'static str {
+        Strategy::ThreadBundles.name()
+    }
+
+    fn search(
+        &self,
+        operands: &[String],
+        bin_operations: &[String],
+        number: Rational,
+    ) -> SearchOutcome {
+        generator_outcome(generator::search_number_by_thread_bundles(
+            operands.to_vec(),
+            bin_operations.to_vec(),
+            number,
+        ))
+    }
+}
+
+struct OperationBundlesSearch;
+
+impl SearchStrategy for OperationBundlesSearch {
+    fn name(&self) -> &'static str {
+        Strategy::OperationBundles.name()
+    }
+
+    fn search(
+        &self,
+        operands: &[String],
+        bin_operations: &[String],
+        number: Rational,
+    ) -> SearchOutcome {
+        generator_outcome(generator::search_number_by_operation_bundles(
+            operands.to_vec(),
+            bin_operations.to_vec(),
+            number,
+        ))
+    }
+}
+
+struct SnapshotsSearch {
+    permutations: Permutations,
+    scheme_range: Option<SchemeRange>,
+}
+
+impl SearchStrategy for SnapshotsSearch {
+    fn name(&self) -> &'static str {
+        Strategy::Snapshots.name()
+    }
+
+    fn search(
+        &self,
+        operands: &[String],
+        bin_operations: &[String],
+        number: Rational,
+    ) -> SearchOutcome {
+        let schemes = generate_schemes(operands.to_vec(), self.permutations, self.scheme_range);
+        generator_outcome(generator::search_number_by_snapshots(
+            schemes,
+            bin_operations.to_vec(),
+            number,
+        ))
+    }
+}
+
+struct RecursiveSearch {
+    settings: crazy_representation::Settings,
+}
+
+impl SearchStrategy for RecursiveSearch {
+    fn name(&self) -> &'static str {
+        Strategy::Recursive.name()
+    }
+
+    fn search(
+        &self,
+        operands: &[String],
+        bin_operations: &[String],
+        number: Rational,
+    ) -> SearchOutcome {
+        let result = crazy_representation::search_number(
+            operands.to_vec(),
+            bin_operations.to_vec(),
+            number,
+            &self.settings,
+        );
+        SearchOutcome {
+            result,
+            evaluated: crazy_representation::get_evaluated(),
+            pruned: 0,
+            dedup_peak: 0,
+            skipped: crazy_representation::get_skipped(),
+            cut: 0,
+        }
+    }
+}